@@ -0,0 +1,317 @@
+// Reports the panics of a Rust program to Crash Recorder, as "self-crashed"
+// records carrying the message, location and backtrace:
+//
+//     crash_recorder_client::Reporter::new("my-tool")
+//         .what("running")
+//         .install();
+//
+// The record goes over the recorder's socket; when it isn't running, it is
+// written to the spool directory and picked up on its next start. No
+// dependencies, so any tool can afford it.
+
+use std::backtrace::{Backtrace, BacktraceStatus};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+
+// the recorder answers at once, a stuck one must not hold the panicking program
+const REPLY_TIMEOUT: Duration = Duration::from_secs(2);
+
+
+#[derive(Debug, Clone)]
+pub struct Reporter {
+    application: String,
+    what: Option<String>,
+    tags: Vec<String>,
+    socket: Option<PathBuf>,
+    spool: Option<PathBuf>,
+}
+
+
+// What was sent, or why nothing could be.
+#[derive(Debug)]
+pub enum Delivery {
+    Sent,
+    Spooled(PathBuf),
+    Failed(std::io::Error),
+}
+
+
+impl Reporter {
+    pub fn new(application: &str) -> Reporter {
+        Reporter {
+            application: application.to_string(),
+            what: None,
+            tags: vec![],
+            socket: None,
+            spool: None,
+        }
+    }
+
+    // an activity name of the recorder: "running", "testing", "debugging"...
+    pub fn what(mut self, what: &str) -> Reporter {
+        self.what = Some(what.to_string());
+        self
+    }
+
+    pub fn tag(mut self, tag: &str) -> Reporter {
+        self.tags.push(tag.to_string());
+        self
+    }
+
+    // instead of the recorder's, e.g. for tests
+    pub fn socket<P: Into<PathBuf>>(mut self, path: P) -> Reporter {
+        self.socket = Some(path.into());
+        self
+    }
+
+    pub fn spool<P: Into<PathBuf>>(mut self, path: P) -> Reporter {
+        self.spool = Some(path.into());
+        self
+    }
+
+    // Reports each panic, then lets the hook that was there (the default
+    // message on stderr...) run as before.
+    pub fn install(self) {
+        let previous = std::panic::take_hook();
+
+        std::panic::set_hook(Box::new(move |info| {
+            let payload = info.payload();
+            let message = payload.downcast_ref::<&str>().map(|message| message.to_string())
+                .or_else(|| payload.downcast_ref::<String>().cloned())
+                .unwrap_or_else(|| "Box<dyn Any>".into());
+            let location = info.location()
+                .map(|location| format!("{}:{}:{}", location.file(), location.line(), location.column()));
+
+            self.report_panic(&message, location.as_deref(), &Backtrace::force_capture());
+
+            previous(info);
+        }));
+    }
+
+    // Same as a panic would, for errors the program handles but wants recorded.
+    pub fn report_panic(&self, message: &str, location: Option<&str>, backtrace: &Backtrace) -> Delivery {
+        let thread = std::thread::current();
+        let thread = thread.name().unwrap_or("<unnamed>");
+
+        let mut description = message.to_string();
+        if let Some(location) = location {
+            description = format!("{} ({})", description, location);
+        }
+
+        // in the format of the Rust runtime, which the recorder parses
+        let stack_trace = match backtrace.status() {
+            BacktraceStatus::Captured => Some(format!("thread '{}' panicked at {}:\n{}\nstack backtrace:\n{}",
+                                                      thread,
+                                                      location.unwrap_or("<unknown>"),
+                                                      message,
+                                                      backtrace)),
+            _ => None,
+        };
+
+        self.send(&description, stack_trace.as_deref())
+    }
+
+    fn send(&self, description: &str, stack_trace: Option<&str>) -> Delivery {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+
+        let mut fields = vec![
+            ("how", json_string("self-crashed")),
+            ("when", json_string(&rfc3339(now))),
+            ("application", json_string(&self.application)),
+            ("description", json_string(description)),
+        ];
+        if let Some(what) = &self.what {
+            fields.push(("what", json_string(what)));
+        }
+        if !self.tags.is_empty() {
+            let tags: Vec<String> = self.tags.iter().map(|tag| json_string(tag)).collect();
+            fields.push(("tags", format!("[{}]", tags.join(","))));
+        }
+        if let Some(stack_trace) = stack_trace {
+            fields.push(("stack_trace", json_string(stack_trace)));
+        }
+
+        let record = json_object(&fields);
+
+        fields.insert(0, ("command", json_string("report")));
+        let command = json_object(&fields);
+
+        let socket = self.socket.clone().unwrap_or_else(socket_path);
+        if send_command(&socket, &command).unwrap_or(false) {
+            return Delivery::Sent;
+        }
+
+        let spool = self.spool.clone().unwrap_or_else(spool_dir);
+        match write_spool_file(&spool, now, &record) {
+            Ok(path) => Delivery::Spooled(path),
+            Err(err) => Delivery::Failed(err),
+        }
+    }
+}
+
+
+// true when the recorder took it
+#[cfg(unix)]
+fn send_command(socket: &Path, command: &str) -> std::io::Result<bool> {
+    let mut stream = std::os::unix::net::UnixStream::connect(socket)?;
+    stream.set_read_timeout(Some(REPLY_TIMEOUT))?;
+    stream.set_write_timeout(Some(REPLY_TIMEOUT))?;
+    writeln!(stream, "{}", command)?;
+
+    let mut reply = String::new();
+    BufReader::new(stream).read_line(&mut reply)?;
+
+    Ok(reply.replace(' ', "").contains("\"ok\":true"))
+}
+
+
+#[cfg(not(unix))]
+fn send_command(_socket: &Path, _command: &str) -> std::io::Result<bool> {
+    Ok(false)
+}
+
+
+// written aside then renamed, the recorder only reads complete ".json" files
+fn write_spool_file(dir: &Path, now: Duration, record: &str) -> std::io::Result<PathBuf> {
+    std::fs::create_dir_all(dir)?;
+
+    let name = format!("{:020}-{}", now.as_nanos(), std::process::id());
+    let temporary = dir.join(format!("{}.tmp", name));
+    let path = dir.join(format!("{}.json", name));
+
+    std::fs::write(&temporary, record)?;
+    std::fs::rename(&temporary, &path)?;
+
+    Ok(path)
+}
+
+
+// the same places as the recorder, see its `ipc::socket_path` and `spool::dir`
+fn socket_path() -> PathBuf {
+    if let Some(path) = std::env::var_os("CRASH_RECORDER_SOCKET") {
+        return path.into();
+    }
+
+    match std::env::var_os("XDG_RUNTIME_DIR").filter(|_| cfg!(target_os = "linux")) {
+        Some(dir) => PathBuf::from(dir).join("crashrecorder").join("recorder.sock"),
+        None => std::env::temp_dir().join(format!("crash-recorder-{}.sock", user_name())),
+    }
+}
+
+
+fn spool_dir() -> PathBuf {
+    if let Some(path) = std::env::var_os("CRASH_RECORDER_SPOOL") {
+        return path.into();
+    }
+
+    let home = std::env::var_os("HOME").map(PathBuf::from);
+
+    let data_dir =
+        if cfg!(target_os = "macos") {
+            home.map(|home| home.join("Library/Application Support/rs.evolix1.Crash-Recorder"))
+        }
+        else if cfg!(windows) {
+            std::env::var_os("APPDATA").map(|dir| PathBuf::from(dir).join("evolix1").join("Crash Recorder").join("data"))
+        }
+        else {
+            std::env::var_os("XDG_DATA_HOME").map(PathBuf::from)
+                .or_else(|| home.map(|home| home.join(".local/share")))
+                .map(|dir| dir.join("crashrecorder"))
+        };
+
+    match data_dir {
+        Some(dir) => dir.join("spool"),
+        None => std::env::temp_dir().join(format!("crash-recorder-{}-spool", user_name())),
+    }
+}
+
+
+fn user_name() -> String {
+    ["USER", "USERNAME", "LOGNAME"]
+        .iter()
+        .filter_map(|var| std::env::var(var).ok())
+        .map(|name| name.trim().to_string())
+        .find(|name| !name.is_empty())
+        .unwrap_or_else(|| "unknown".into())
+}
+
+
+fn json_object(fields: &[(&str, String)]) -> String {
+    let fields: Vec<String> = fields.iter()
+        .map(|(name, value)| format!("{}:{}", json_string(name), value))
+        .collect();
+
+    format!("{{{}}}", fields.join(","))
+}
+
+
+fn json_string(text: &str) -> String {
+    let mut quoted = String::with_capacity(text.len() + 2);
+    quoted.push('"');
+
+    for c in text.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c if (c as u32) < 0x20 => quoted.push_str(&format!("\\u{:04x}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+
+    quoted.push('"');
+    quoted
+}
+
+
+// "2020-05-17T09:41:07.123456789+00:00", the days to date part is from
+// [http://howardhinnant.github.io/date_algorithms.html#civil_from_days]
+fn rfc3339(since_epoch: Duration) -> String {
+    let secs = since_epoch.as_secs() as i64;
+    let (days, time) = (secs.div_euclid(86400), secs.rem_euclid(86400));
+
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!("{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:09}+00:00",
+            year, month, day,
+            time / 3600, time / 60 % 60, time % 60,
+            since_epoch.subsec_nanos())
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+
+    #[test]
+    fn rfc3339_dates() {
+        assert_eq!(rfc3339(Duration::from_secs(0)), "1970-01-01T00:00:00.000000000+00:00");
+        assert_eq!(rfc3339(Duration::new(951_782_400 + 3723, 5)), "2000-02-29T01:02:03.000000005+00:00");
+        assert_eq!(rfc3339(Duration::from_secs(951_868_800)), "2000-03-01T00:00:00.000000000+00:00");
+        // not a leap year, the century rule
+        assert_eq!(rfc3339(Duration::from_secs(4_107_456_000)), "2100-02-28T00:00:00.000000000+00:00");
+        assert_eq!(rfc3339(Duration::from_secs(4_107_542_400)), "2100-03-01T00:00:00.000000000+00:00");
+    }
+
+    #[test]
+    fn json_string_escapes() {
+        assert_eq!(json_string("plain"), r#""plain""#);
+        assert_eq!(json_string("say \"hi\" \\o/"), r#""say \"hi\" \\o/""#);
+        assert_eq!(json_string("a\nb\r\tc"), r#""a\nb\r\tc""#);
+        assert_eq!(json_string("\u{0}\u{1b}[0m\u{1f}"), r#""\u0000\u001b[0m\u001f""#);
+        assert_eq!(json_string("\u{7f} é ✓"), "\"\u{7f} é ✓\"");
+    }
+}
@@ -1,13 +1,37 @@
-use std::path::PathBuf;
+use std::collections::{BTreeMap, HashMap};
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+use std::time::{Duration, SystemTime};
+use chrono::{DateTime, Utc};
 use serde_derive::{Deserialize, Serialize};
 use directories::ProjectDirs;
 
 use crate::record::Record;
+use crate::import::ImportReport;
+use crate::sessions::{Session, SessionSource};
+use crate::issues::{IssueRef, IssueStatus, KnownIssue};
 
 
 #[derive(Debug, Default, Clone, Serialize, Deserialize)]
 pub struct AppData {
     pub records: Vec<Record>,
+    #[serde(default)]
+    pub sessions: Vec<Session>,
+}
+
+
+// records.json as we last read or wrote it, to tell our changes from those
+// made meanwhile by a script or another instance
+#[derive(Debug, Clone, Default)]
+pub struct OnDisk {
+    pub path: PathBuf,
+    pub data: AppData,
+    // of the file's contents, compared rather than the modification time, which
+    // some file systems keep to the second or more; none when `data` isn't what
+    // the file holds, so that the next save merges with it
+    pub hash: Option<u64>,
 }
 
 
@@ -24,46 +48,125 @@ pub enum SaveError {
     FileError,
     WriteError,
     FormatError,
+    Locked,
 }
 
 
+// records.json.lock, held by whoever is writing records.json. Other writers
+// (scripts, the command line) should do the same to not lose records: create
+// it exclusively (O_CREAT | O_EXCL), retrying while it exists, then read,
+// change and write records.json (through a temporary file renamed over it),
+// and remove the lock. One older than `LOCK_STALE_SECS` is taken as left by a
+// dead writer and removed. Without it, a write landing while we save is lost.
+struct Lock(PathBuf);
+
+
+// chosen in the settings, read by everything stored next to records.json
+static DATA_DIR: RwLock<Option<PathBuf>> = RwLock::new(None);
+
+// how often records.json is looked at for changes made by others
+pub const RELOAD_INTERVAL_SECS: i64 = 2;
+
+// a writer holding the lock longer than that has died with it
+const LOCK_STALE_SECS: u64 = 10;
+const LOCK_WAIT_SECS: u64 = 5;
+
+
 // modified from 'iced/example/todos'
 impl AppData {
-    fn path() -> PathBuf {
-        let mut path = match ProjectDirs::from("rs", "evolix1", "Crash Recorder") {
+    pub fn set_data_dir(dir: Option<PathBuf>) {
+        if let Ok(mut data_dir) = DATA_DIR.write() {
+            *data_dir = dir;
+        }
+    }
+
+    pub fn data_dir() -> PathBuf {
+        Self::resolve_data_dir(DATA_DIR.read().ok().and_then(|dir| dir.clone()))
+    }
+
+    // `Settings::data_dir`, or the platform's
+    pub fn resolve_data_dir(dir: Option<PathBuf>) -> PathBuf {
+        if let Some(dir) = dir {
+            return dir;
+        }
+
+        match ProjectDirs::from("rs", "evolix1", "Crash Recorder") {
             Some(project_dirs) => project_dirs.data_dir().into(),
             None => std::env::current_dir().unwrap_or(PathBuf::new())
-        };
+        }
+    }
+
+    pub fn path() -> PathBuf {
+        let mut path = Self::data_dir();
 
         path.push("records.json");
 
         path
     }
 
-    pub async fn load() -> Result<AppData, LoadError> {
-        use async_std::prelude::*;
+    // the data to work on, and the file it came from
+    pub async fn load(sync_dir: Option<PathBuf>) -> Result<(AppData, OnDisk), LoadError> {
+        let path = Self::path();
+        let disk = match Self::read(&path).await {
+            Ok(disk) => disk,
+            // first run on this machine, the shared folder may still have records
+            Err(LoadError::FileError) if sync_dir.is_some() => OnDisk { path, ..OnDisk::default() },
+            Err(err) => return Err(err),
+        };
 
-        let mut contents = String::new();
+        let mut data = disk.data.clone();
 
-        let mut file = async_std::fs::File::open(Self::path())
-            .await
-            .map_err(|_| LoadError::FileError)?;
+        if let Some(dir) = sync_dir {
+            crate::sync::pull(&dir, &mut data).await;
+            // publish right away, so others don't wait for our next record
+            let _ = crate::sync::push(&dir, &data).await;
+        }
+
+        Ok((data, disk))
+    }
 
-        file.read_to_string(&mut contents)
+    async fn read(path: &Path) -> Result<OnDisk, LoadError> {
+        let contents = async_std::fs::read_to_string(path)
             .await
             .map_err(|_| LoadError::FileError)?;
 
-        serde_json::from_str(&contents)
-            .map_err(|_| LoadError::FormatError)
+        Self::parse(path, &contents)
     }
 
-    pub async fn save(self) -> Result<(), SaveError> {
-        use async_std::prelude::*;
+    fn parse(path: &Path, contents: &str) -> Result<OnDisk, LoadError> {
+        let mut data: AppData = serde_json::from_str(contents)
+            .map_err(|_| LoadError::FormatError)?;
 
-        let json = serde_json::to_string_pretty(&self)
-            .map_err(|_| SaveError::FormatError)?;
+        // records from before the sync existed were all made here
+        for record in data.records.iter_mut() {
+            record.stamp_origin();
+            record.drop_unsafe_attachments();
+        }
 
-        let path = Self::path();
+        Ok(OnDisk { path: path.to_path_buf(), data, hash: Some(content_hash(contents)) })
+    }
+
+    // None unless the file changed since `known` and can be read, else the
+    // file and `ours` merged with it
+    pub async fn read_if_changed(known: OnDisk, ours: AppData) -> Option<(OnDisk, AppData)> {
+        let contents = async_std::fs::read_to_string(&known.path).await.ok()?;
+
+        if known.hash == Some(content_hash(&contents)) {
+            return None;
+        }
+
+        let theirs = Self::parse(&known.path, &contents).ok()?;
+        let merged = AppData::merged(&known.data, &ours, &theirs.data);
+
+        Some((theirs, merged))
+    }
+
+    // Written under the lock, after merging what changed on disk since `base`
+    // was read; what was written is returned. The path is that of `base`, so a
+    // save still pending when the data folder changes goes where the data was
+    // loaded from.
+    pub async fn save(self, base: OnDisk, sync_dir: Option<PathBuf>) -> Result<OnDisk, SaveError> {
+        let path = base.path.clone();
 
         if let Some(dir) = path.parent() {
             async_std::fs::create_dir_all(dir)
@@ -71,19 +174,342 @@ impl AppData {
                 .map_err(|_| SaveError::DirectoryError)?;
         }
 
-        {
-            let mut file = async_std::fs::File::create(path)
-                .await
-                .map_err(|_| SaveError::FileError)?;
+        let disk = {
+            let _lock = Lock::acquire(&path).await?;
+
+            let data = match Self::read(&path).await {
+                Ok(theirs) if theirs.hash != base.hash => AppData::merged(&base.data, &self, &theirs.data),
+                _ => self,
+            };
+
+            let json = serde_json::to_string_pretty(&data)
+                .map_err(|_| SaveError::FormatError)?;
 
-            file.write_all(json.as_bytes())
+            // a reader never sees half a file
+            let temporary = path.with_extension("json.tmp");
+            async_std::fs::write(&temporary, json.as_bytes())
                 .await
                 .map_err(|_| SaveError::WriteError)?;
+            async_std::fs::rename(&temporary, &path)
+                .await
+                .map_err(|_| SaveError::FileError)?;
+
+            OnDisk { path, data, hash: Some(content_hash(&json)) }
+        };
+
+        if let Some(dir) = sync_dir {
+            crate::sync::push(&dir, &disk.data).await?;
         }
 
         // This is a simple way to save at most once every couple seconds
         async_std::task::sleep(std::time::Duration::from_secs(2)).await;
 
+        Ok(disk)
+    }
+
+    // Into another data folder: the records (merged with any found there), their
+    // attachments and the hooks log. The outbox and the draft are left to the
+    // window, which writes them wherever the data folder is.
+    pub async fn move_data(self, from: PathBuf, to: PathBuf) -> Result<(), SaveError> {
+        let target = OnDisk { path: to.join("records.json"), ..OnDisk::default() };
+        self.save(target, None).await?;
+
+        let attachments = from.join("attachments");
+        if let Ok(mut records) = async_std::fs::read_dir(&attachments).await {
+            use async_std::prelude::*;
+
+            while let Some(Ok(record)) = records.next().await {
+                let mut files = match async_std::fs::read_dir(record.path()).await {
+                    Ok(files) => files,
+                    Err(_) => continue,
+                };
+
+                let record_dir = to.join("attachments").join(record.file_name());
+                async_std::fs::create_dir_all(&record_dir)
+                    .await
+                    .map_err(|_| SaveError::DirectoryError)?;
+                while let Some(Ok(file)) = files.next().await {
+                    let path: PathBuf = file.path().into();
+                    move_file(&path, &record_dir.join(file.file_name())).await?;
+                }
+
+                let _ = async_std::fs::remove_dir(record.path()).await;
+            }
+
+            let _ = async_std::fs::remove_dir(&attachments).await;
+        }
+
+        if async_std::fs::metadata(from.join("hooks.log")).await.is_ok() {
+            move_file(&from.join("hooks.log"), &to.join("hooks.log")).await?;
+        }
+
+        // only once everything is over there
+        for name in ["records.json", "outbox.json", "draft.json"].iter() {
+            let _ = async_std::fs::remove_file(from.join(name)).await;
+        }
+
         Ok(())
     }
+
+    // Three-way merge of `ours` and `theirs`, both changed from `base`: additions
+    // from both sides are kept, a removal only wins over an unchanged record,
+    // and when both sides changed the same record ours wins.
+    pub fn merged(base: &AppData, ours: &AppData, theirs: &AppData) -> AppData {
+        let mut records = merge_lists(&base.records, &ours.records, &theirs.records, |record| record.id.clone());
+        records.sort_by_key(|record| record.when);
+
+        let mut sessions = merge_lists(&base.sessions, &ours.sessions, &theirs.sessions,
+                                       |session| (session.application.clone(), session.started));
+        sessions.sort_by_key(|session| session.started);
+
+        AppData { records, sessions }
+    }
+
+    pub fn record(&self, id: &str) -> Option<&Record> {
+        self.records.iter().find(|record| record.id == id)
+    }
+
+    pub fn record_mut(&mut self, id: &str) -> Option<&mut Record> {
+        self.records.iter_mut().find(|record| record.id == id)
+    }
+
+    pub fn merge(&mut self, records: Vec<Record>) -> ImportReport {
+        let mut report = ImportReport::default();
+
+        for mut record in records {
+            record.stamp_imported();
+            record.drop_unsafe_attachments();
+
+            if self.records.iter().any(|existing| existing.id == record.id || existing.same_incident(&record)) {
+                report.skipped += 1;
+            }
+            else {
+                self.records.push(record);
+                report.added += 1;
+            }
+        }
+
+        self.records.sort_by_key(|record| record.when);
+
+        report
+    }
+
+    pub fn open_session(&self, application: &str) -> Option<&Session> {
+        self.sessions.iter().rev().find(|session| session.is_open() && session.application == application)
+    }
+
+    // false when it was running already
+    pub fn start_session(&mut self, application: &str, source: SessionSource, now: DateTime<Utc>) -> bool {
+        if application.is_empty() || self.open_session(application).is_some() {
+            return false;
+        }
+
+        self.sessions.push(Session {
+            application: application.into(),
+            source,
+            started: now,
+            ended: None,
+        });
+
+        true
+    }
+
+    pub fn end_session(&mut self, application: &str, now: DateTime<Utc>) -> Option<&Session> {
+        let session = self.sessions.iter_mut()
+            .rev()
+            .find(|session| session.is_open() && session.application == application)?;
+
+        session.ended = Some(now);
+
+        Some(session)
+    }
+
+    // tags used so far, most used first, for autocompletion and filters
+    pub fn tags(&self) -> Vec<String> {
+        let mut counts: BTreeMap<&str, usize> = BTreeMap::new();
+
+        for tag in self.records.iter().flat_map(|record| record.tags.iter()) {
+            *counts.entry(tag).or_insert(0) += 1;
+        }
+
+        let mut tags: Vec<(&str, usize)> = counts.into_iter().collect();
+        tags.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+
+        tags.into_iter().map(|(tag, _)| tag.to_string()).collect()
+    }
+
+    // one entry per tracker and id, most recently seen first
+    pub fn known_issues(&self) -> Vec<KnownIssue> {
+        let mut known: Vec<KnownIssue> = vec![];
+
+        for record in self.records.iter() {
+            for issue in record.issues.iter() {
+                match known.iter_mut().find(|known| known.issue.same_issue(issue)) {
+                    Some(known) => {
+                        known.records += 1;
+                        known.last = known.last.max(record.when);
+                    },
+                    None => known.push(KnownIssue {
+                        issue: issue.clone(),
+                        records: 1,
+                        last: record.when,
+                    }),
+                }
+            }
+        }
+
+        known.sort_by(|a, b| b.last.cmp(&a.last));
+
+        known
+    }
+
+    // on every record linked to it, the number of records changed
+    pub fn set_issue_status(&mut self, issue: &IssueRef, status: IssueStatus) -> usize {
+        let mut changed = 0;
+
+        for linked in self.records.iter_mut().flat_map(|record| record.issues.iter_mut()) {
+            if linked.same_issue(issue) && linked.status != status {
+                linked.status = status;
+                changed += 1;
+            }
+        }
+
+        changed
+    }
+
+    // every application seen in a session, running ones first
+    pub fn applications(&self) -> Vec<String> {
+        let mut applications: Vec<String> = vec![];
+
+        for session in self.sessions.iter().rev() {
+            if !applications.contains(&session.application) {
+                applications.push(session.application.clone());
+            }
+        }
+
+        applications.sort_by_key(|application| self.open_session(application).is_none());
+
+        applications
+    }
+}
+
+
+impl Lock {
+    async fn acquire(path: &Path) -> Result<Lock, SaveError> {
+        let lock = path.with_extension("json.lock");
+        let deadline = SystemTime::now() + Duration::from_secs(LOCK_WAIT_SECS);
+
+        loop {
+            let created = async_std::fs::OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(&lock)
+                .await;
+
+            if created.is_ok() {
+                return Ok(Lock(lock));
+            }
+
+            let stale = async_std::fs::metadata(&lock)
+                .await
+                .and_then(|metadata| metadata.modified())
+                .ok()
+                .and_then(|modified| modified.elapsed().ok())
+                .map_or(false, |age| age > Duration::from_secs(LOCK_STALE_SECS));
+
+            if stale {
+                let _ = async_std::fs::remove_file(&lock).await;
+            }
+            else if SystemTime::now() > deadline {
+                return Err(SaveError::Locked);
+            }
+            else {
+                async_std::task::sleep(Duration::from_millis(100)).await;
+            }
+        }
+    }
+}
+
+
+impl Drop for Lock {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.0);
+    }
+}
+
+
+// Renamed, or copied then removed when on another device. A file already at
+// `to` is kept, the one at `from` stays then.
+async fn move_file(from: &Path, to: &Path) -> Result<(), SaveError> {
+    if async_std::fs::metadata(to).await.is_ok() {
+        return Ok(());
+    }
+
+    if async_std::fs::rename(from, to).await.is_err() {
+        async_std::fs::copy(from, to)
+            .await
+            .map_err(|_| SaveError::FileError)?;
+        let _ = async_std::fs::remove_file(from).await;
+    }
+
+    Ok(())
+}
+
+
+fn content_hash(contents: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    contents.hash(&mut hasher);
+    hasher.finish()
+}
+
+
+// Items are told apart by `key`.
+fn merge_lists<T, K, F>(base: &[T], ours: &[T], theirs: &[T], key: F) -> Vec<T>
+    where T: Clone + PartialEq,
+          K: Eq + Hash,
+          F: Fn(&T) -> K,
+{
+    let base_items: HashMap<K, &T> = base.iter().map(|item| (key(item), item)).collect();
+    let our_items: HashMap<K, &T> = ours.iter().map(|item| (key(item), item)).collect();
+    let their_items: HashMap<K, &T> = theirs.iter().map(|item| (key(item), item)).collect();
+
+    let mut merged = vec![];
+
+    for item in theirs {
+        let k = key(item);
+        match (base_items.get(&k), our_items.get(&k)) {
+            // removed here, unless changed there
+            (Some(original), None) if *original == item => (),
+            (Some(original), Some(mine)) if original != mine => merged.push((*mine).clone()),
+            _ => merged.push(item.clone()),
+        }
+    }
+
+    for item in ours {
+        let k = key(item);
+        if their_items.contains_key(&k) {
+            continue;
+        }
+
+        match base_items.get(&k) {
+            // removed there, unless changed here
+            Some(original) if *original == item => (),
+            _ => merged.push(item.clone()),
+        }
+    }
+
+    merged
+}
+
+
+// Keep names built from ids or user input usable as a single path component.
+pub fn safe_file_name(name: &str) -> String {
+    // "." and ".." are components, but not of a file
+    if name.chars().all(|c| c == '.') {
+        return name.replace('.', "_");
+    }
+
+    name.chars()
+        .map(|c| if c.is_alphanumeric() || "@-_.".contains(c) { c } else { '_' })
+        .collect()
 }
@@ -0,0 +1,102 @@
+use std::path::PathBuf;
+use serde_derive::{Deserialize, Serialize};
+
+use crate::app_data::{AppData, safe_file_name};
+
+
+pub const MAX_FILE_SIZE: u64 = 10 * 1024 * 1024;
+pub const MAX_RECORD_SIZE: u64 = 50 * 1024 * 1024;
+
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Attachment {
+    pub name: String,
+    pub size: u64,
+}
+
+
+#[derive(Debug, Clone)]
+pub enum AttachError {
+    FileError,
+    TooLarge,
+    CopyError,
+}
+
+
+// Each record owns a folder named after its id, next to records.json.
+pub fn record_dir(record_id: &str) -> PathBuf {
+    let mut path = AppData::data_dir();
+
+    path.push("attachments");
+    path.push(safe_file_name(record_id));
+
+    path
+}
+
+
+impl Attachment {
+    pub fn path(&self, record_id: &str) -> PathBuf {
+        record_dir(record_id).join(safe_file_name(&self.name))
+    }
+
+    // as `attach` names them; one from another machine's JSON could point
+    // anywhere ("../../.bashrc")
+    pub fn is_safe(&self) -> bool {
+        !self.name.is_empty() && safe_file_name(&self.name) == self.name
+    }
+}
+
+
+pub async fn attach(record_id: String,
+                    source: PathBuf,
+                    existing: Vec<Attachment>) -> Result<Attachment, AttachError> {
+    let metadata = async_std::fs::metadata(&source)
+        .await
+        .map_err(|_| AttachError::FileError)?;
+
+    if !metadata.is_file() {
+        return Err(AttachError::FileError);
+    }
+
+    let used: u64 = existing.iter().map(|attachment| attachment.size).sum();
+
+    if metadata.len() > MAX_FILE_SIZE || used + metadata.len() > MAX_RECORD_SIZE {
+        return Err(AttachError::TooLarge);
+    }
+
+    let base_name = source.file_name()
+        .map(|name| safe_file_name(&name.to_string_lossy()))
+        .ok_or(AttachError::FileError)?;
+
+    // attaching two "ide.log" from different places must not overwrite the first
+    let mut name = base_name.clone();
+    let mut n = 2;
+    while existing.iter().any(|attachment| attachment.name == name) {
+        name = format!("{}-{}", n, base_name);
+        n += 1;
+    }
+
+    let dir = record_dir(&record_id);
+
+    async_std::fs::create_dir_all(&dir)
+        .await
+        .map_err(|_| AttachError::CopyError)?;
+
+    let size = async_std::fs::copy(&source, dir.join(&name))
+        .await
+        .map_err(|_| AttachError::CopyError)?;
+
+    Ok(Attachment { name, size })
+}
+
+
+pub async fn remove(record_id: String, attachment: Attachment) {
+    let _ = async_std::fs::remove_file(attachment.path(&record_id)).await;
+}
+
+
+pub async fn remove_all(record_ids: Vec<String>) {
+    for record_id in record_ids {
+        let _ = async_std::fs::remove_dir_all(record_dir(&record_id)).await;
+    }
+}
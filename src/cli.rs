@@ -0,0 +1,48 @@
+// Command line shortcuts to the socket of the running window, e.g. from a launcher script:
+//   crash-recorder started my-app && my-app; crash-recorder stopped my-app
+// None when the arguments are not a command, to start the window as usual.
+pub fn run(args: Vec<String>) -> Option<i32> {
+    let command = match args.iter().map(String::as_str).collect::<Vec<_>>().as_slice() {
+        ["started", application] => serde_json::json!({ "command": "app-started", "application": application }),
+        ["stopped", application] => serde_json::json!({ "command": "app-stopped", "application": application }),
+        ["started"] | ["stopped"] => {
+            eprintln!("usage: crash-recorder started|stopped <application>");
+            return Some(2);
+        },
+        _ => return None,
+    };
+
+    match send(command) {
+        Ok(response) => {
+            println!("{}", response);
+            let ok = serde_json::from_str::<serde_json::Value>(&response)
+                .map_or(false, |response| response["ok"] == true);
+            Some(if ok { 0 } else { 1 })
+        },
+        Err(err) => {
+            eprintln!("crash-recorder: is the recorder running? {}", err);
+            Some(1)
+        },
+    }
+}
+
+
+#[cfg(unix)]
+fn send(command: serde_json::Value) -> std::io::Result<String> {
+    use std::io::{BufRead, BufReader, Write};
+    use std::os::unix::net::UnixStream;
+
+    let mut stream = UnixStream::connect(crate::ipc::socket_path())?;
+    writeln!(stream, "{}", command)?;
+
+    let mut response = String::new();
+    BufReader::new(stream).read_line(&mut response)?;
+
+    Ok(response.trim().to_string())
+}
+
+
+#[cfg(not(unix))]
+fn send(_command: serde_json::Value) -> std::io::Result<String> {
+    Err(std::io::Error::new(std::io::ErrorKind::Other, "no socket on this platform"))
+}
@@ -0,0 +1,165 @@
+use std::collections::BTreeSet;
+use chrono::{DateTime, Duration, Utc};
+use serde_derive::Serialize;
+
+use crate::record::{Record, HowItWasStopped, WhatWasHappening, dt_serde};
+
+
+// share of description words two records need in common to land together
+const SIMILARITY: f64 = 0.5;
+
+// words saying nothing about the crash itself
+const STOP_WORDS: [&str; 24] = [
+    "the", "and", "was", "were", "when", "while", "with", "then", "after", "before",
+    "again", "just", "that", "this", "had", "has", "have", "for", "from", "into",
+    "not", "but", "it's", "its",
+];
+
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Trend {
+    New,        // nothing before this week
+    Rising,
+    Steady,
+    Falling,
+    Quiet,      // nothing this week
+}
+
+
+// Records that look like the same crash: same application, stop reason and
+// activity, and the same signature (stack trace or signal) or close enough
+// descriptions.
+#[derive(Debug, Clone, Serialize)]
+pub struct Cluster {
+    // id of its first record, stays while that record is kept
+    pub id: String,
+    pub application: Option<String>,
+    pub how: HowItWasStopped,
+    pub what: Option<WhatWasHappening>,
+    pub signature: Option<String>,
+    // compared instead of the signature against records with a stack trace
+    // when only one side has one
+    pub signal: Option<String>,
+    pub traced: bool,
+    // description words of the first record
+    pub words: Vec<String>,
+    pub record_ids: Vec<String>,
+    #[serde(serialize_with="dt_serde::serialize")]
+    pub first: DateTime<Utc>,
+    #[serde(serialize_with="dt_serde::serialize")]
+    pub last: DateTime<Utc>,
+    // records in the last 7 days, and in the 7 days before
+    pub this_week: usize,
+    pub last_week: usize,
+}
+
+
+impl Cluster {
+    fn new(record: &Record, signature: Option<String>, words: Vec<String>) -> Self {
+        Self {
+            id: record.id.clone(),
+            application: record.application.clone(),
+            how: record.how,
+            what: record.what.clone(),
+            signature,
+            signal: record.signal.clone(),
+            traced: record.stack_trace.is_some(),
+            words,
+            record_ids: vec![],
+            first: record.when,
+            last: record.when,
+            this_week: 0,
+            last_week: 0,
+        }
+    }
+
+    fn accepts(&self, record: &Record, signature: &Option<String>, words: &[String]) -> bool {
+        if self.application != record.application || self.how != record.how || self.what != record.what {
+            return false;
+        }
+
+        match (&self.signature, signature) {
+            (Some(signature), Some(other)) if self.traced == record.stack_trace.is_some() => signature == other,
+            (Some(_), Some(_)) => self.signal.is_some() && self.signal == record.signal,
+            (None, None) => similarity(&self.words, words) >= SIMILARITY,
+            _ => false,
+        }
+    }
+
+    pub fn count(&self) -> usize {
+        self.record_ids.len()
+    }
+
+    pub fn trend(&self) -> Trend {
+        match (self.last_week, self.this_week) {
+            (_, 0) => Trend::Quiet,
+            (0, _) => Trend::New,
+            (before, now) if now > before => Trend::Rising,
+            (before, now) if now < before => Trend::Falling,
+            _ => Trend::Steady,
+        }
+    }
+}
+
+
+// most records first, then most recent
+pub fn compute(records: &[Record], now: DateTime<Utc>) -> Vec<Cluster> {
+    let mut clusters: Vec<Cluster> = vec![];
+    let week_ago = now - Duration::days(7);
+    let two_weeks_ago = now - Duration::days(14);
+
+    let mut sorted: Vec<&Record> = records.iter().collect();
+    sorted.sort_by_key(|record| record.when);
+
+    for record in sorted {
+        let signature = record.signature();
+        let words = description_words(&record.description);
+        let i = match clusters.iter().position(|cluster| cluster.accepts(record, &signature, &words)) {
+            Some(i) => i,
+            None => {
+                clusters.push(Cluster::new(record, signature, words));
+                clusters.len() - 1
+            },
+        };
+
+        let cluster = &mut clusters[i];
+        cluster.record_ids.push(record.id.clone());
+        cluster.last = record.when;
+
+        if record.when > week_ago {
+            cluster.this_week += 1;
+        }
+        else if record.when > two_weeks_ago {
+            cluster.last_week += 1;
+        }
+    }
+
+    clusters.sort_by(|a, b| b.count().cmp(&a.count()).then(b.last.cmp(&a.last)));
+    clusters
+}
+
+
+// lowercase words without numbers, addresses or filler, each once and sorted
+pub fn description_words(description: &str) -> Vec<String> {
+    description.split(|c: char| !c.is_alphanumeric() && c != '\'' && c != '_')
+        .map(|word| word.trim_matches('\'').to_lowercase())
+        .filter(|word| word.chars().count() >= 3)
+        .filter(|word| !word.chars().any(|c| c.is_ascii_digit()))
+        .filter(|word| !STOP_WORDS.contains(&word.as_str()))
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect()
+}
+
+
+// words in common over words in either; two empty descriptions are alike
+fn similarity(a: &[String], b: &[String]) -> f64 {
+    let union = a.iter().chain(b.iter()).collect::<BTreeSet<_>>().len();
+    if union == 0 {
+        return 1.0;
+    }
+
+    let common = a.iter().filter(|word| b.contains(word)).count();
+    common as f64 / union as f64
+}
@@ -0,0 +1,52 @@
+use std::path::PathBuf;
+
+use crate::app_data::{AppData, SaveError};
+use crate::record::Record;
+
+
+// The record being filled in the window, kept on disk so a pending
+// Frozen/Busy start survives the recorder (or the desktop) going down.
+fn path() -> PathBuf {
+    let mut path = AppData::data_dir();
+
+    path.push("draft.json");
+
+    path
+}
+
+
+// nothing worth restoring: no timer running and nothing typed
+pub fn is_blank(record: &Record) -> bool {
+    record.frozen.is_none() && record.busy.is_none() && record.description.trim().is_empty()
+}
+
+
+pub async fn load() -> Option<Record> {
+    async_std::fs::read_to_string(path())
+        .await
+        .ok()
+        .and_then(|contents| serde_json::from_str::<Record>(&contents).ok())
+        .filter(|record| !is_blank(record))
+}
+
+
+// a blank record removes the file
+pub async fn save(record: Record) -> Result<(), SaveError> {
+    if is_blank(&record) {
+        return match async_std::fs::remove_file(path()).await {
+            Err(err) if err.kind() != std::io::ErrorKind::NotFound => Err(SaveError::WriteError),
+            _ => Ok(()),
+        };
+    }
+
+    let json = serde_json::to_string_pretty(&record)
+        .map_err(|_| SaveError::FormatError)?;
+
+    async_std::fs::create_dir_all(AppData::data_dir())
+        .await
+        .map_err(|_| SaveError::DirectoryError)?;
+
+    async_std::fs::write(path(), json)
+        .await
+        .map_err(|_| SaveError::WriteError)
+}
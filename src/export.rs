@@ -0,0 +1,63 @@
+use std::path::PathBuf;
+
+use crate::app_data::{AppData, safe_file_name};
+
+
+#[derive(Debug, Clone, Default)]
+pub struct ExportReport {
+    pub records: usize,
+    // "<record id>/<file name>" of the attachments not on this machine, as
+    // those of records made elsewhere and pulled by the sync
+    pub missing: Vec<String>,
+}
+
+
+#[derive(Debug, Clone)]
+pub enum ExportError {
+    DirectoryError,
+    FormatError,
+    WriteError,
+    CopyError,
+}
+
+
+// Write a self-contained folder: records.json plus attachments/<record id>/...,
+// the same layout as the data directory. Importing its records.json brings the
+// attachments back too, see `import::records`.
+pub async fn export(data: AppData, dest: PathBuf) -> Result<ExportReport, ExportError> {
+    let json = serde_json::to_string_pretty(&data)
+        .map_err(|_| ExportError::FormatError)?;
+
+    async_std::fs::create_dir_all(&dest)
+        .await
+        .map_err(|_| ExportError::DirectoryError)?;
+
+    async_std::fs::write(dest.join("records.json"), json)
+        .await
+        .map_err(|_| ExportError::WriteError)?;
+
+    let mut report = ExportReport { records: data.records.len(), ..ExportReport::default() };
+
+    for record in data.records.iter().filter(|record| !record.attachments.is_empty()) {
+        let dir = dest.join("attachments").join(safe_file_name(&record.id));
+
+        async_std::fs::create_dir_all(&dir)
+            .await
+            .map_err(|_| ExportError::DirectoryError)?;
+
+        for attachment in record.attachments.iter() {
+            let source = attachment.path(&record.id);
+
+            if async_std::fs::metadata(&source).await.is_err() {
+                report.missing.push(format!("{}/{}", record.id, attachment.name));
+                continue;
+            }
+
+            async_std::fs::copy(source, dir.join(safe_file_name(&attachment.name)))
+                .await
+                .map_err(|_| ExportError::CopyError)?;
+        }
+    }
+
+    Ok(report)
+}
@@ -0,0 +1,217 @@
+use std::io::{Read, Write};
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+use chrono::{DateTime, Utc};
+use serde_derive::{Deserialize, Serialize};
+
+use crate::app_data::AppData;
+use crate::record::{Record, dt_serde};
+
+
+// kept in the log, the rest of a chatty hook's output is dropped
+const MAX_OUTPUT_SIZE: usize = 4096;
+
+
+// A shell command run after each committed record. It gets the record as JSON
+// on stdin and its main fields as CRASH_RECORDER_* environment variables.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Hook {
+    pub name: String,
+    pub command: String,
+    pub timeout_secs: u64,
+}
+
+
+impl Default for Hook {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            command: String::new(),
+            timeout_secs: 30,
+        }
+    }
+}
+
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum HookStatus {
+    Exited(i32),
+    Killed,     // by a signal, but not by us
+    TimedOut,
+    NotStarted,
+}
+
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HookOutcome {
+    pub hook: String,
+    pub record_id: String,
+    #[serde(
+        serialize_with="dt_serde::serialize",
+        deserialize_with="dt_serde::deserialize")]
+    pub started: DateTime<Utc>,
+    pub status: HookStatus,
+    pub output: String,
+}
+
+
+impl HookOutcome {
+    pub fn succeeded(&self) -> bool {
+        match self.status {
+            HookStatus::Exited(code) => code == 0,
+            _ => false,
+        }
+    }
+}
+
+
+pub async fn run(hook: Hook, record: Record) -> HookOutcome {
+    let outcome = async_std::task::spawn_blocking(move || run_blocking(hook, record)).await;
+
+    append_to_log(&outcome).await;
+
+    outcome
+}
+
+
+fn run_blocking(hook: Hook, record: Record) -> HookOutcome {
+    let started = Utc::now();
+    let outcome = |status, output| HookOutcome {
+        hook: hook.name.clone(),
+        record_id: record.id.clone(),
+        started,
+        status,
+        output,
+    };
+
+    let mut child = match shell(&hook.command)
+        .env("CRASH_RECORDER_ID", &record.id)
+        .env("CRASH_RECORDER_HOW", record.how.name())
+        .env("CRASH_RECORDER_WHAT", record.what.as_ref().map_or("", |what| what.name()))
+        .env("CRASH_RECORDER_WHEN", record.when.to_rfc3339())
+        .env("CRASH_RECORDER_DESCRIPTION", &record.description)
+        .env("CRASH_RECORDER_TAGS", record.tags.join(","))
+        .env("CRASH_RECORDER_HOST", &record.host)
+        .env("CRASH_RECORDER_USER", &record.user)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn() {
+        Ok(child) => child,
+        Err(err) => return outcome(HookStatus::NotStarted, err.to_string()),
+    };
+
+    // Pipes are fed and drained aside, so a hook ignoring stdin or filling
+    // stdout can't block us past the timeout. Those threads are never joined:
+    // a process the hook left behind (`cmd &`) may keep the pipes open after
+    // the shell is gone, killing it only stops the shell.
+    let json = serde_json::to_string_pretty(&record).unwrap_or_default();
+    if let Some(mut stdin) = child.stdin.take() {
+        std::thread::spawn(move || {
+            let _ = stdin.write_all(json.as_bytes());
+        });
+    }
+
+    let (sender, outputs) = std::sync::mpsc::channel();
+    let mut readers = 0;
+    let pipes: Vec<Option<Box<dyn Read + Send>>> = vec![
+        child.stdout.take().map(|pipe| Box::new(pipe) as Box<dyn Read + Send>),
+        child.stderr.take().map(|pipe| Box::new(pipe) as Box<dyn Read + Send>),
+    ];
+    for (i, pipe) in pipes.into_iter().enumerate() {
+        if let Some(pipe) = pipe {
+            let sender = sender.clone();
+            readers += 1;
+            std::thread::spawn(move || {
+                let _ = sender.send((i, drain(pipe)));
+            });
+        }
+    }
+
+    let deadline = Instant::now() + Duration::from_secs(hook.timeout_secs);
+    let status = loop {
+        match child.try_wait() {
+            Ok(Some(status)) => break status.code().map_or(HookStatus::Killed, HookStatus::Exited),
+            Ok(None) if Instant::now() < deadline => std::thread::sleep(Duration::from_millis(50)),
+            _ => {
+                let _ = child.kill();
+                let _ = child.wait();
+                break HookStatus::TimedOut;
+            }
+        }
+    };
+
+    // what is still open at the deadline (or right after a timeout) is left out
+    let output_deadline = std::cmp::max(deadline, Instant::now()) + Duration::from_millis(500);
+    let mut drained = [String::new(), String::new()];
+    for _ in 0..readers {
+        let left = output_deadline.saturating_duration_since(Instant::now());
+        match outputs.recv_timeout(left) {
+            Ok((i, text)) => drained[i] = text,
+            Err(_) => break,
+        }
+    }
+
+    let mut output = drained.concat();
+
+    if output.len() > MAX_OUTPUT_SIZE {
+        let mut end = MAX_OUTPUT_SIZE;
+        while !output.is_char_boundary(end) {
+            end -= 1;
+        }
+        output.truncate(end);
+    }
+
+    outcome(status, output)
+}
+
+
+#[cfg(unix)]
+fn shell(command: &str) -> Command {
+    let mut shell = Command::new("sh");
+    shell.arg("-c").arg(command);
+    shell
+}
+
+
+#[cfg(not(unix))]
+fn shell(command: &str) -> Command {
+    let mut shell = Command::new("cmd");
+    shell.arg("/C").arg(command);
+    shell
+}
+
+
+fn drain(mut pipe: impl Read) -> String {
+    let mut bytes = vec![];
+    let _ = pipe.read_to_end(&mut bytes);
+
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+
+
+// hooks.log, next to records.json: one JSON outcome per line
+async fn append_to_log(outcome: &HookOutcome) {
+    use async_std::prelude::*;
+
+    let mut line = match serde_json::to_string(outcome) {
+        Ok(line) => line,
+        Err(_) => return,
+    };
+    line.push('\n');
+
+    let dir = AppData::data_dir();
+    let _ = async_std::fs::create_dir_all(&dir).await;
+
+    let file = async_std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(dir.join("hooks.log"))
+        .await;
+
+    if let Ok(mut file) = file {
+        let _ = file.write_all(line.as_bytes()).await;
+    }
+}
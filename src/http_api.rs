@@ -0,0 +1,587 @@
+use chrono::{DateTime, Utc};
+
+use crate::app_data::AppData;
+use crate::record::{Record, RecordInput, HowItWasStopped, WhatWasHappening, normalize_name};
+use crate::stats::Stats;
+use crate::clusters;
+
+
+// Endpoints, all JSON:
+//   GET    /records?how=&what=&since=&until=&q=&limit=
+//   POST   /records
+//   GET    /records/<id>
+//   PUT    /records/<id>
+//   DELETE /records/<id>
+//   GET    /stats
+//   GET    /signatures
+// Only from clients naming us by a local Host (no web page behind DNS
+// rebinding), POST and PUT only with an "application/json" body (no form a
+// web page could send without asking).
+#[derive(Debug, Clone)]
+pub struct HttpRequest {
+    pub method: String,
+    pub path: String,
+    pub query: String,
+    pub body: String,
+    reply: std::sync::mpsc::Sender<HttpResponse>,
+}
+
+
+#[derive(Debug, Clone)]
+pub struct HttpResponse {
+    pub status: u16,
+    pub body: String,
+}
+
+
+// What handling a request did to the data, so the window knows to save.
+pub struct Handled {
+    pub response: HttpResponse,
+    pub changed: bool,
+    pub deleted: Vec<String>,
+}
+
+
+impl HttpRequest {
+    pub fn reply(&self, response: HttpResponse) {
+        let _ = self.reply.send(response);
+    }
+}
+
+
+impl HttpResponse {
+    fn json<T: serde::Serialize>(status: u16, value: &T) -> HttpResponse {
+        HttpResponse {
+            status,
+            body: serde_json::to_string_pretty(value).unwrap_or_default(),
+        }
+    }
+
+    pub fn error(status: u16, message: &str) -> HttpResponse {
+        HttpResponse::json(status, &serde_json::json!({ "error": message }))
+    }
+
+    fn reason(&self) -> &'static str {
+        match self.status {
+            200 => "OK",
+            201 => "Created",
+            204 => "No Content",
+            400 => "Bad Request",
+            404 => "Not Found",
+            405 => "Method Not Allowed",
+            403 => "Forbidden",
+            413 => "Payload Too Large",
+            415 => "Unsupported Media Type",
+            503 => "Service Unavailable",
+            _ => "Internal Server Error",
+        }
+    }
+}
+
+
+// The response to send without handling the request, if any.
+pub fn refuse(method: &str, host: Option<&str>, content_type: Option<&str>, port: u16) -> Option<HttpResponse> {
+    let local = [format!("127.0.0.1:{}", port), format!("localhost:{}", port)];
+    if !host.map_or(false, |host| local.iter().any(|local| host.trim().eq_ignore_ascii_case(local))) {
+        return Some(HttpResponse::error(403, "the Host header must be 127.0.0.1 or localhost with the port"));
+    }
+
+    let json = content_type
+        .and_then(|value| value.split(';').next())
+        .map_or(false, |value| value.trim().eq_ignore_ascii_case("application/json"));
+    if (method == "POST" || method == "PUT") && !json {
+        return Some(HttpResponse::error(415, "the body must be sent as application/json"));
+    }
+
+    None
+}
+
+
+pub fn handle(data: &mut AppData, request: &HttpRequest, now: DateTime<Utc>) -> Handled {
+    let unchanged = |response| Handled { response, changed: false, deleted: vec![] };
+    let segments: Vec<&str> = request.path.split('/').filter(|s| !s.is_empty()).collect();
+
+    match (request.method.as_str(), segments.as_slice()) {
+        ("GET", ["stats"]) => {
+            unchanged(HttpResponse::json(200, &Stats::compute(&data.records, &data.sessions, now)))
+        },
+        ("GET", ["signatures"]) => {
+            unchanged(HttpResponse::json(200, &clusters::compute(&data.records, now)))
+        },
+        ("GET", ["records"]) => {
+            match filter(&data.records, &request.query) {
+                Ok(records) => unchanged(HttpResponse::json(200, &records)),
+                Err(message) => unchanged(HttpResponse::error(400, &message)),
+            }
+        },
+        ("POST", ["records"]) => {
+            let input: RecordInput = match serde_json::from_str(&request.body) {
+                Ok(input) => input,
+                Err(err) => return unchanged(HttpResponse::error(400, &err.to_string())),
+            };
+
+            let mut record = Record { when: now, ..Record::default() };
+            if let Err(message) = input.apply(&mut record) {
+                return unchanged(HttpResponse::error(400, &message));
+            }
+            record.stamp_origin();
+
+            if data.records.iter().any(|existing| existing.id == record.id) {
+                return unchanged(HttpResponse::error(400, "a record already exists at this time"));
+            }
+
+            let response = HttpResponse::json(201, &record);
+            data.records.push(record);
+            data.records.sort_by_key(|record| record.when);
+
+            Handled { response, changed: true, deleted: vec![] }
+        },
+        ("GET", ["records", id]) => {
+            match data.record(id) {
+                Some(record) => unchanged(HttpResponse::json(200, record)),
+                None => unchanged(HttpResponse::error(404, "no such record")),
+            }
+        },
+        ("PUT", ["records", id]) => {
+            let input: RecordInput = match serde_json::from_str(&request.body) {
+                Ok(input) => input,
+                Err(err) => return unchanged(HttpResponse::error(400, &err.to_string())),
+            };
+
+            match data.record_mut(id) {
+                Some(record) => {
+                    // the id stays, even if "when" moves
+                    if let Err(message) = input.apply(record) {
+                        return unchanged(HttpResponse::error(400, &message));
+                    }
+                    let response = HttpResponse::json(200, record);
+                    data.records.sort_by_key(|record| record.when);
+
+                    Handled { response, changed: true, deleted: vec![] }
+                },
+                None => unchanged(HttpResponse::error(404, "no such record")),
+            }
+        },
+        ("DELETE", ["records", id]) => {
+            let len = data.records.len();
+            data.records.retain(|record| record.id != *id);
+
+            if data.records.len() == len {
+                return unchanged(HttpResponse::error(404, "no such record"));
+            }
+
+            Handled {
+                response: HttpResponse { status: 204, body: String::new() },
+                changed: true,
+                deleted: vec![id.to_string()],
+            }
+        },
+        (_, ["stats"]) | (_, ["signatures"]) | (_, ["records"]) | (_, ["records", _]) => {
+            unchanged(HttpResponse::error(405, "method not allowed"))
+        },
+        _ => unchanged(HttpResponse::error(404, "not found")),
+    }
+}
+
+
+fn filter<'a>(records: &'a [Record], query: &str) -> Result<Vec<&'a Record>, String> {
+    let mut how = None;
+    let mut what = None;
+    let mut since = None;
+    let mut until = None;
+    let mut text = None;
+    let mut tags = vec![];
+    let mut reported = None;
+    let mut limit = None;
+
+    let date = |value: &str| DateTime::parse_from_rfc3339(value)
+        .map(|dt| dt.with_timezone(&Utc))
+        .map_err(|_| format!("not an RFC 3339 date: {}", value));
+
+    for (key, value) in query.split('&').filter(|pair| !pair.is_empty()).map(split_pair) {
+        match key.as_str() {
+            "how" => how = Some(serde_json::from_value::<HowItWasStopped>(value.clone().into())
+                .map_err(|_| format!("unknown how: {}", value))?),
+            "what" => what = Some(serde_json::from_value::<WhatWasHappening>(value.clone().into())
+                .map_err(|_| format!("unknown what: {}", value))?),
+            "since" => since = Some(date(&value)?),
+            "until" => until = Some(date(&value)?),
+            "q" => text = Some(value.to_lowercase()),
+            // repeated for records having all of them
+            "tag" => tags.extend(normalize_name(&value)),
+            "reported" => reported = Some(value.parse::<bool>().map_err(|_| "reported must be true or false".to_string())?),
+            "limit" => limit = Some(value.parse::<usize>().map_err(|_| "limit must be a number".to_string())?),
+            _ => return Err(format!("unknown parameter: {}", key)),
+        }
+    }
+
+    let mut records: Vec<&Record> = records.iter()
+        .filter(|record| how.map_or(true, |how| record.how == how))
+        .filter(|record| what.as_ref().map_or(true, |what| record.what.as_ref() == Some(what)))
+        .filter(|record| since.map_or(true, |since| record.when >= since))
+        .filter(|record| until.map_or(true, |until| record.when < until))
+        .filter(|record| text.as_ref().map_or(true, |text| record.description.to_lowercase().contains(text)))
+        .filter(|record| tags.iter().all(|tag| record.tags.contains(tag)))
+        .filter(|record| reported.map_or(true, |reported| record.issues.is_empty() != reported))
+        .collect();
+
+    // most recent first, so `limit` keeps the latest ones
+    records.reverse();
+    if let Some(limit) = limit {
+        records.truncate(limit);
+    }
+
+    Ok(records)
+}
+
+
+fn split_pair(pair: &str) -> (String, String) {
+    let mut parts = pair.splitn(2, '=');
+    let key = percent_decode(parts.next().unwrap_or(""));
+    let value = percent_decode(parts.next().unwrap_or(""));
+
+    (key, value)
+}
+
+
+fn percent_decode(text: &str) -> String {
+    let mut bytes = vec![];
+    let mut input = text.bytes();
+
+    while let Some(byte) = input.next() {
+        match byte {
+            b'+' => bytes.push(b' '),
+            b'%' => {
+                let hex: Vec<u8> = input.by_ref().take(2).collect();
+                match std::str::from_utf8(&hex).ok().and_then(|hex| u8::from_str_radix(hex, 16).ok()) {
+                    Some(decoded) => bytes.push(decoded),
+                    None => {
+                        bytes.push(b'%');
+                        bytes.extend(hex);
+                    }
+                }
+            },
+            byte => bytes.push(byte),
+        }
+    }
+
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+
+
+pub fn listen(port: Option<u16>) -> iced::Subscription<HttpRequest> {
+    match port {
+        Some(port) => iced::Subscription::from_recipe(Server(port)),
+        None => iced::Subscription::none(),
+    }
+}
+
+
+struct Server(u16);
+
+
+impl<H, I> iced_native::subscription::Recipe<H, I> for Server
+where
+    H: std::hash::Hasher,
+{
+    type Output = HttpRequest;
+
+    fn hash(&self, state: &mut H) {
+        use std::hash::Hash;
+
+        std::any::TypeId::of::<Self>().hash(state);
+        self.0.hash(state);
+    }
+
+    fn stream(
+        self: Box<Self>,
+        _input: iced::futures::stream::BoxStream<'static, I>,
+    ) -> iced::futures::stream::BoxStream<'static, Self::Output> {
+        use iced::futures::stream::StreamExt;
+
+        let (sender, receiver) = iced::futures::channel::mpsc::unbounded();
+        let port = self.0;
+        std::thread::spawn(move || server::run(port, sender));
+
+        receiver.boxed()
+    }
+}
+
+
+// Just enough HTTP/1.1 for local scripts: one request per connection, no chunked bodies.
+mod server {
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::{Ipv4Addr, TcpListener, TcpStream};
+    use std::time::Duration;
+    use iced::futures::channel::mpsc::UnboundedSender;
+
+    use super::{HttpRequest, HttpResponse, refuse};
+
+
+    const MAX_BODY_SIZE: usize = 1024 * 1024;
+    const REPLY_TIMEOUT: Duration = Duration::from_secs(5);
+
+
+    pub fn run(port: u16, sender: UnboundedSender<HttpRequest>) {
+        // never reachable from the network
+        let listener = match TcpListener::bind((Ipv4Addr::LOCALHOST, port)) {
+            Ok(listener) => listener,
+            Err(err) => {
+                eprintln!("crash-recorder: cannot listen on port {}: {}", port, err);
+                return;
+            }
+        };
+
+        for stream in listener.incoming().filter_map(|stream| stream.ok()) {
+            let sender = sender.clone();
+            std::thread::spawn(move || serve(stream, port, sender));
+        }
+    }
+
+    fn serve(stream: TcpStream, port: u16, sender: UnboundedSender<HttpRequest>) {
+        let _ = stream.set_read_timeout(Some(REPLY_TIMEOUT));
+
+        let mut writer = match stream.try_clone() {
+            Ok(writer) => writer,
+            Err(_) => return,
+        };
+
+        let response = match read_request(stream, port) {
+            Ok((method, target, body)) => {
+                let mut target = target.splitn(2, '?');
+                let path = target.next().unwrap_or("/").to_string();
+                let query = target.next().unwrap_or("").to_string();
+                let (reply, response) = std::sync::mpsc::channel();
+
+                if sender.unbounded_send(HttpRequest { method, path, query, body, reply }).is_err() {
+                    return; // window closed
+                }
+
+                response.recv_timeout(REPLY_TIMEOUT)
+                    .unwrap_or_else(|_| HttpResponse::error(503, "no answer from the recorder"))
+            },
+            Err(response) => response,
+        };
+
+        let _ = write!(writer,
+                       "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                       response.status,
+                       response.reason(),
+                       response.body.len(),
+                       response.body);
+    }
+
+    fn read_request(stream: TcpStream, port: u16) -> Result<(String, String, String), HttpResponse> {
+        let bad_request = || HttpResponse::error(400, "malformed request");
+        let mut reader = BufReader::new(stream);
+        let mut line = String::new();
+
+        reader.read_line(&mut line).map_err(|_| bad_request())?;
+        let mut request_line = line.split_whitespace();
+        let method = request_line.next().ok_or_else(bad_request)?.to_string();
+        let target = request_line.next().ok_or_else(bad_request)?.to_string();
+
+        let mut content_length = 0;
+        let mut host = None;
+        let mut content_type = None;
+        loop {
+            line.clear();
+            reader.read_line(&mut line).map_err(|_| bad_request())?;
+
+            let header = line.trim_end();
+            if header.is_empty() {
+                break;
+            }
+
+            let mut parts = header.splitn(2, ':');
+            let name = parts.next().unwrap_or("").trim();
+            let value = parts.next().map(|value| value.trim().to_string());
+            if name.eq_ignore_ascii_case("content-length") {
+                content_length = value
+                    .and_then(|value| value.parse().ok())
+                    .ok_or_else(bad_request)?;
+            }
+            else if name.eq_ignore_ascii_case("host") {
+                host = value;
+            }
+            else if name.eq_ignore_ascii_case("content-type") {
+                content_type = value;
+            }
+        }
+
+        if let Some(response) = refuse(&method, host.as_deref(), content_type.as_deref(), port) {
+            return Err(response);
+        }
+
+        if content_length > MAX_BODY_SIZE {
+            return Err(HttpResponse::error(413, "body too large"));
+        }
+
+        let mut body = vec![0; content_length];
+        reader.read_exact(&mut body).map_err(|_| bad_request())?;
+        let body = String::from_utf8(body).map_err(|_| bad_request())?;
+
+        Ok((method, target, body))
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+
+    fn at(text: &str) -> DateTime<Utc> {
+        text.parse().unwrap()
+    }
+
+
+    fn request(method: &str, target: &str, body: &str) -> HttpRequest {
+        let mut target = target.splitn(2, '?');
+        let (reply, _) = std::sync::mpsc::channel();
+
+        HttpRequest {
+            method: method.into(),
+            path: target.next().unwrap_or("/").into(),
+            query: target.next().unwrap_or("").into(),
+            body: body.into(),
+            reply,
+        }
+    }
+
+    fn data() -> AppData {
+        let record = |id: &str, when, how, description: &str, tags: &[&str]| Record {
+            id: id.into(),
+            when: at(when),
+            how,
+            description: description.into(),
+            tags: tags.iter().map(|tag| tag.to_string()).collect(),
+            ..Record::default()
+        };
+
+        AppData {
+            records: vec![
+                record("a", "2020-05-01T12:00:00Z", HowItWasStopped::SelfCrashed, "Crashed on save", &["io"]),
+                record("b", "2020-05-02T12:00:00Z", HowItWasStopped::ManuallyKilled, "Frozen on load", &["io", "ui"]),
+                record("c", "2020-05-03T12:00:00Z", HowItWasStopped::SelfCrashed, "Crashed on load", &[]),
+            ],
+            ..AppData::default()
+        }
+    }
+
+    fn ids(handled: &Handled) -> Vec<String> {
+        let records: Vec<Record> = serde_json::from_str(&handled.response.body).unwrap();
+        records.into_iter().map(|record| record.id).collect()
+    }
+
+    #[test]
+    fn routes() {
+        let mut data = data();
+        let now = Utc::now();
+
+        assert_eq!(handle(&mut data, &request("GET", "/stats", ""), now).response.status, 200);
+        assert_eq!(handle(&mut data, &request("GET", "/signatures", ""), now).response.status, 200);
+        assert_eq!(handle(&mut data, &request("GET", "/records/b", ""), now).response.status, 200);
+        assert_eq!(handle(&mut data, &request("GET", "/records/z", ""), now).response.status, 404);
+        assert_eq!(handle(&mut data, &request("GET", "/nothing", ""), now).response.status, 404);
+        assert_eq!(handle(&mut data, &request("PATCH", "/records", ""), now).response.status, 405);
+        assert_eq!(handle(&mut data, &request("POST", "/stats", ""), now).response.status, 405);
+    }
+
+    #[test]
+    fn filters() {
+        let mut data = data();
+        let now = Utc::now();
+        let get = |data: &mut AppData, target| handle(data, &request("GET", target, ""), now);
+
+        assert_eq!(ids(&get(&mut data, "/records")), vec!["c", "b", "a"]);
+        assert_eq!(ids(&get(&mut data, "/records?how=self-crashed")), vec!["c", "a"]);
+        assert_eq!(ids(&get(&mut data, "/records?q=on+LOAD")), vec!["c", "b"]);
+        assert_eq!(ids(&get(&mut data, "/records?tag=io&tag=%23UI")), vec!["b"]);
+        assert_eq!(ids(&get(&mut data, "/records?since=2020-05-02T00:00:00Z&until=2020-05-03T00:00:00Z")), vec!["b"]);
+        assert_eq!(ids(&get(&mut data, "/records?limit=1")), vec!["c"]);
+
+        assert_eq!(get(&mut data, "/records?how=exploded").response.status, 400);
+        assert_eq!(get(&mut data, "/records?since=yesterday").response.status, 400);
+        assert_eq!(get(&mut data, "/records?limit=many").response.status, 400);
+        assert_eq!(get(&mut data, "/records?colour=red").response.status, 400);
+    }
+
+    #[test]
+    fn post() {
+        let mut data = data();
+        let now = at("2020-05-04T08:00:00Z");
+        let body = r#"{"description":"Out of memory","tags":["Big File"]}"#;
+
+        let handled = handle(&mut data, &request("POST", "/records", body), now);
+        assert_eq!(handled.response.status, 201);
+        assert!(handled.changed);
+        let record = data.records.last().unwrap();
+        assert_eq!(record.when, now);
+        assert_eq!(record.tags, vec!["big-file"]);
+        assert!(!record.id.is_empty());
+
+        let again = handle(&mut data, &request("POST", "/records", body), now);
+        assert_eq!(again.response.status, 400);
+        assert!(!again.changed);
+
+        for body in &["not json", r#"{"descripton":"typo"}"#, r#"{"how":"exploded"}"#, r#"{"stack_trace":"no frames"}"#] {
+            let handled = handle(&mut data, &request("POST", "/records", body), now);
+            assert_eq!(handled.response.status, 400, "{}", body);
+            assert!(!handled.changed);
+        }
+        assert_eq!(data.records.len(), 4);
+    }
+
+    #[test]
+    fn put_changes_only_given_fields() {
+        let mut data = data();
+        let now = Utc::now();
+
+        let handled = handle(&mut data, &request("PUT", "/records/a", r#"{"description":"Crashed on export","when":"2020-05-10T00:00:00Z"}"#), now);
+        assert_eq!(handled.response.status, 200);
+        assert!(handled.changed);
+
+        let record = data.record("a").unwrap();
+        assert_eq!(record.description, "Crashed on export");
+        assert_eq!(record.how, HowItWasStopped::SelfCrashed);
+        assert_eq!(record.tags, vec!["io"]);
+        // kept in time order, under the same id
+        assert_eq!(data.records.last().unwrap().id, "a");
+
+        let handled = handle(&mut data, &request("PUT", "/records/a", r#"{"how":"exploded"}"#), now);
+        assert_eq!(handled.response.status, 400);
+        assert_eq!(data.record("a").unwrap().how, HowItWasStopped::SelfCrashed);
+
+        assert_eq!(handle(&mut data, &request("PUT", "/records/z", "{}"), now).response.status, 404);
+    }
+
+    #[test]
+    fn delete() {
+        let mut data = data();
+        let now = Utc::now();
+
+        let handled = handle(&mut data, &request("DELETE", "/records/b", ""), now);
+        assert_eq!(handled.response.status, 204);
+        assert_eq!(handled.deleted, vec!["b"]);
+        assert!(data.record("b").is_none());
+
+        assert_eq!(handle(&mut data, &request("DELETE", "/records/b", ""), now).response.status, 404);
+    }
+
+    #[test]
+    fn refuses_foreign_hosts_and_forms() {
+        let status = |method, host, content_type| refuse(method, host, content_type, 8080).map(|response| response.status);
+
+        assert_eq!(status("GET", Some("127.0.0.1:8080"), None), None);
+        assert_eq!(status("GET", Some("LocalHost:8080"), None), None);
+        assert_eq!(status("GET", Some("evil.example:8080"), None), Some(403));
+        assert_eq!(status("GET", Some("127.0.0.1:9090"), None), Some(403));
+        assert_eq!(status("GET", None, None), Some(403));
+
+        assert_eq!(status("POST", Some("localhost:8080"), Some("application/json; charset=utf-8")), None);
+        assert_eq!(status("PUT", Some("localhost:8080"), Some("text/plain")), Some(415));
+        assert_eq!(status("POST", Some("localhost:8080"), None), Some(415));
+        assert_eq!(status("DELETE", Some("localhost:8080"), None), None);
+    }
+}
@@ -0,0 +1,198 @@
+use std::collections::HashMap;
+use std::sync::RwLock;
+use serde_derive::{Deserialize, Serialize};
+
+
+// tr!("key") is the &'static str of a plain message,
+// tr!("key", name = value...) fills the {name} placeholders of a template
+macro_rules! tr {
+    ($key:expr) => {
+        $crate::i18n::text($key)
+    };
+    ($key:expr, $($name:ident = $value:expr),+ $(,)?) => {
+        $crate::i18n::format($key, None, &[$((stringify!($name), $value.to_string())),+])
+    };
+}
+
+
+// like tr! with a form chosen after `count`, which is also the {count} placeholder
+macro_rules! trn {
+    ($key:expr, $count:expr $(, $name:ident = $value:expr)* $(,)?) => {
+        $crate::i18n::format($key,
+                             Some($count as u64),
+                             &[("count", $count.to_string()) $(, (stringify!($name), $value.to_string()))*])
+    };
+}
+
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Locale {
+    En,
+    Fr,
+}
+
+
+impl Locale {
+    pub const ALL: [Locale; 2] = [
+        Locale::En,
+        Locale::Fr,
+    ];
+
+    // in its own language, as offered in the settings
+    pub fn label(self) -> &'static str {
+        match self {
+            Locale::En => "English",
+            Locale::Fr => "Français",
+        }
+    }
+
+    fn code(self) -> &'static str {
+        match self {
+            Locale::En => "en",
+            Locale::Fr => "fr",
+        }
+    }
+
+    fn catalog(self) -> &'static str {
+        match self {
+            Locale::En => include_str!("en.json"),
+            Locale::Fr => include_str!("fr.json"),
+        }
+    }
+
+    fn plural_form(self, count: u64) -> PluralForm {
+        match self {
+            Locale::En if count == 1 => PluralForm::One,
+            Locale::Fr if count <= 1 => PluralForm::One,
+            _ => PluralForm::Other,
+        }
+    }
+}
+
+
+enum PluralForm {
+    One,
+    Other,
+}
+
+
+// "key": "text", or "key": {"one": "...", "other": "..."} for a count
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum Message {
+    Text(String),
+    Plural { one: String, other: String },
+}
+
+
+type Catalog = HashMap<String, Message>;
+
+
+struct Catalogs {
+    locale: Locale,
+    current: &'static Catalog,
+    // English, for the messages missing from a translation
+    fallback: &'static Catalog,
+}
+
+
+static CATALOGS: RwLock<Option<Catalogs>> = RwLock::new(None);
+
+
+// from LC_ALL, LC_MESSAGES or LANG ("fr_FR.UTF-8"...), English otherwise
+pub fn detect() -> Locale {
+    let language = ["LC_ALL", "LC_MESSAGES", "LANG"].iter()
+        .filter_map(|name| std::env::var(name).ok())
+        .find(|value| !value.is_empty())
+        .unwrap_or_default()
+        .to_lowercase();
+
+    Locale::ALL.iter()
+        .copied()
+        .find(|locale| language.starts_with(locale.code()))
+        .unwrap_or(Locale::En)
+}
+
+
+pub fn set_locale(locale: Locale) {
+    let mut catalogs = match CATALOGS.write() {
+        Ok(catalogs) => catalogs,
+        Err(_) => return,
+    };
+
+    if catalogs.as_ref().map_or(false, |catalogs| catalogs.locale == locale) {
+        return;
+    }
+
+    // leaked so `text` can hand out &'static str, once per locale switch
+    let fallback = match catalogs.as_ref() {
+        Some(catalogs) => catalogs.fallback,
+        None => parse(Locale::En),
+    };
+
+    *catalogs = Some(Catalogs {
+        locale,
+        current: if locale == Locale::En { fallback } else { parse(locale) },
+        fallback,
+    });
+}
+
+
+fn parse(locale: Locale) -> &'static Catalog {
+    let catalog = serde_json::from_str(locale.catalog()).unwrap_or_else(|err| {
+        eprintln!("crash-recorder: broken {:?} catalog: {}", locale, err);
+        Catalog::new()
+    });
+
+    Box::leak(Box::new(catalog))
+}
+
+
+fn lookup(key: &str, count: Option<u64>) -> Option<&'static str> {
+    let catalogs = CATALOGS.read().ok()?;
+    let catalogs = catalogs.as_ref()?;
+
+    let (locale, message) = match catalogs.current.get(key) {
+        Some(message) => (catalogs.locale, message),
+        None => (Locale::En, catalogs.fallback.get(key)?),
+    };
+
+    Some(match (message, count.map(|count| locale.plural_form(count))) {
+        (Message::Text(text), _) => text,
+        (Message::Plural { one, .. }, Some(PluralForm::One)) => one,
+        (Message::Plural { other, .. }, _) => other,
+    })
+}
+
+
+// an unknown key shows as itself, which is easier to spot than a blank
+pub fn text(key: &'static str) -> &'static str {
+    lookup(key, None).unwrap_or(key)
+}
+
+
+pub fn format(key: &'static str, count: Option<u64>, args: &[(&str, String)]) -> String {
+    let template = lookup(key, count).unwrap_or(key);
+    let mut result = String::with_capacity(template.len());
+    let mut rest = template;
+
+    while let Some(start) = rest.find('{') {
+        result.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        let end = match rest.find('}') {
+            Some(end) => end,
+            None => break,
+        };
+
+        match args.iter().find(|(name, _)| *name == &rest[1..end]) {
+            Some((_, value)) => result.push_str(value),
+            None => result.push_str(&rest[..=end]),
+        }
+        rest = &rest[end + 1..];
+    }
+
+    result.push_str(rest);
+    result
+}
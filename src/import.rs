@@ -0,0 +1,414 @@
+use std::path::{Path, PathBuf};
+use chrono::{DateTime, NaiveDateTime, TimeZone, Utc};
+
+use crate::app_data::{AppData, safe_file_name};
+use crate::record::{Record, HowItWasStopped, WhatWasHappening, normalize_name};
+
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CsvField {
+    When,
+    How,
+    Description,
+    What,
+    Frozen,
+    Busy,
+}
+
+
+impl CsvField {
+    pub const ALL: [CsvField; 6] = [
+        CsvField::When,
+        CsvField::How,
+        CsvField::Description,
+        CsvField::What,
+        CsvField::Frozen,
+        CsvField::Busy,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            CsvField::When => tr!("import.field.when"),
+            CsvField::How => tr!("import.field.how"),
+            CsvField::Description => tr!("import.field.description"),
+            CsvField::What => tr!("import.field.what"),
+            CsvField::Frozen => tr!("import.field.frozen"),
+            CsvField::Busy => tr!("import.field.busy"),
+        }
+    }
+
+    // header names recognized when guessing the mapping (lowercase)
+    fn aliases(self) -> &'static [&'static str] {
+        match self {
+            CsvField::When => &["when", "date", "time", "timestamp", "datetime"],
+            CsvField::How => &["how", "stopped", "how it was stopped", "reason", "kind"],
+            CsvField::Description => &["description", "comment", "comments", "note", "notes"],
+            CsvField::What => &["what", "activity", "what was happening", "doing"],
+            CsvField::Frozen => &["frozen", "frozen since", "frozen at"],
+            CsvField::Busy => &["busy", "busy since", "busy at"],
+        }
+    }
+}
+
+
+// Which CSV column (by index) feeds each record field.
+#[derive(Debug, Clone, Default)]
+pub struct CsvMapping {
+    columns: [Option<usize>; 6],
+}
+
+
+impl CsvMapping {
+    pub fn guess(headers: &[String]) -> CsvMapping {
+        let mut mapping = CsvMapping::default();
+
+        for field in CsvField::ALL.iter() {
+            mapping.columns[*field as usize] = headers.iter()
+                .position(|header| field.aliases().contains(&header.trim().to_lowercase().as_str()));
+        }
+
+        mapping
+    }
+
+    pub fn get(&self, field: CsvField) -> Option<usize> {
+        self.columns[field as usize]
+    }
+
+    // move the field to the next column, going through "unmapped" after the last one
+    pub fn cycle(&mut self, field: CsvField, column_count: usize) {
+        let next = match self.get(field) {
+            None if column_count > 0 => Some(0),
+            Some(i) if i + 1 < column_count => Some(i + 1),
+            _ => None,
+        };
+
+        self.columns[field as usize] = next;
+    }
+}
+
+
+#[derive(Debug, Clone)]
+pub enum ImportKind {
+    // the rows are kept to tell which ones the mapping can't read
+    Csv { headers: Vec<String>, mapping: CsvMapping, rows: Vec<Vec<String>> },
+    Recorder { count: usize },
+}
+
+
+#[derive(Debug, Clone)]
+pub struct ImportPreview {
+    pub path: PathBuf,
+    pub kind: ImportKind,
+}
+
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ImportReport {
+    pub added: usize,
+    pub skipped: usize,
+}
+
+
+#[derive(Debug, Clone)]
+pub enum ImportError {
+    FileError,
+    FormatError,
+    MappingError,
+}
+
+
+impl ImportPreview {
+    // numbers of the rows (the headers being row 1) that would be skipped
+    // with the current mapping
+    pub fn rejected_rows(&self) -> Vec<usize> {
+        match &self.kind {
+            ImportKind::Csv { mapping, rows, .. } => rows.iter()
+                .enumerate()
+                .filter(|(_, row)| !is_blank(row) && record_from_row(row, mapping).is_none())
+                .map(|(i, _)| i + 2)
+                .collect(),
+            ImportKind::Recorder { .. } => vec![],
+        }
+    }
+}
+
+
+pub async fn preview(path: PathBuf) -> Result<ImportPreview, ImportError> {
+    let contents = read(&path).await?;
+
+    let kind = if is_csv(&path) {
+        let mut rows = parse_csv(&contents);
+        if rows.is_empty() {
+            return Err(ImportError::FormatError);
+        }
+        let headers = rows.remove(0);
+        let mapping = CsvMapping::guess(&headers);
+
+        ImportKind::Csv { headers, mapping, rows }
+    }
+    else {
+        let data: AppData = serde_json::from_str(&contents)
+            .map_err(|_| ImportError::FormatError)?;
+
+        ImportKind::Recorder { count: data.records.len() }
+    };
+
+    Ok(ImportPreview { path, kind })
+}
+
+
+// the rows of `rejected_rows` are left out
+pub async fn records(preview: ImportPreview) -> Result<Vec<Record>, ImportError> {
+    match preview.kind {
+        ImportKind::Csv { mapping, rows, .. } => records_from_csv(&rows, &mapping),
+        ImportKind::Recorder { .. } => {
+            let mut records = serde_json::from_str::<AppData>(&read(&preview.path).await?)
+                .map(|data| data.records)
+                .map_err(|_| ImportError::FormatError)?;
+
+            for record in records.iter_mut() {
+                record.drop_unsafe_attachments();
+            }
+
+            if let Some(dir) = preview.path.parent() {
+                restore_attachments(&dir.join("attachments"), &records).await;
+            }
+
+            Ok(records)
+        }
+    }
+}
+
+
+// From a folder written by `export::export`. Files missing there (left out of
+// the export) are skipped, those already here are kept.
+async fn restore_attachments(dir: &Path, records: &[Record]) {
+    for record in records.iter().filter(|record| !record.id.is_empty()) {
+        let source_dir = dir.join(safe_file_name(&record.id));
+
+        for attachment in record.attachments.iter() {
+            let target = attachment.path(&record.id);
+
+            if async_std::fs::metadata(&target).await.is_ok() {
+                continue;
+            }
+
+            if let Some(parent) = target.parent() {
+                let _ = async_std::fs::create_dir_all(parent).await;
+            }
+
+            let _ = async_std::fs::copy(source_dir.join(safe_file_name(&attachment.name)), target).await;
+        }
+    }
+}
+
+
+async fn read(path: &Path) -> Result<String, ImportError> {
+    async_std::fs::read_to_string(path)
+        .await
+        .map_err(|_| ImportError::FileError)
+}
+
+
+fn is_csv(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .map_or(false, |ext| ext.eq_ignore_ascii_case("csv"))
+}
+
+
+fn records_from_csv(rows: &[Vec<String>], mapping: &CsvMapping) -> Result<Vec<Record>, ImportError> {
+    // without a date there is nothing to deduplicate on, nor to display
+    if mapping.get(CsvField::When).is_none() {
+        return Err(ImportError::MappingError);
+    }
+
+    Ok(rows.iter()
+        .filter(|row| !is_blank(row))
+        .filter_map(|row| record_from_row(row, mapping))
+        .collect())
+}
+
+
+fn is_blank(row: &[String]) -> bool {
+    row.iter().all(|value| value.trim().is_empty())
+}
+
+
+// None when the date can't be read
+fn record_from_row(row: &[String], mapping: &CsvMapping) -> Option<Record> {
+    let cell = |field: CsvField| -> Option<String> {
+        mapping.get(field)
+            .and_then(|i| row.get(i))
+            .map(|value| value.trim().to_string())
+            .filter(|value| !value.is_empty())
+    };
+
+    let when = cell(CsvField::When).and_then(|value| parse_datetime(&value))?;
+
+    Some(Record {
+        frozen: cell(CsvField::Frozen).and_then(|value| parse_datetime(&value)),
+        busy: cell(CsvField::Busy).and_then(|value| parse_datetime(&value)),
+        description: cell(CsvField::Description).unwrap_or_default(),
+        what: cell(CsvField::What).and_then(|value| parse_what(&value)),
+        how: cell(CsvField::How)
+            .and_then(|value| parse_how(&value))
+            .unwrap_or(HowItWasStopped::SelfCrashed),
+        when,
+        ..Record::default()
+    })
+}
+
+
+// Minimal RFC 4180 reader: quoted cells, doubled quotes, CRLF; ';' is accepted
+// as separator when the header line has no ',' (spreadsheet exports in some locales).
+fn parse_csv(contents: &str) -> Vec<Vec<String>> {
+    // spreadsheets start their UTF-8 exports with a byte order mark
+    let contents = contents.strip_prefix('\u{feff}').unwrap_or(contents);
+    let first_line = contents.lines().next().unwrap_or("");
+    let separator = if !first_line.contains(',') && first_line.contains(';') { ';' } else { ',' };
+
+    let mut rows = vec![];
+    let mut row = vec![];
+    let mut cell = String::new();
+    let mut in_quotes = false;
+    let mut chars = contents.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if in_quotes && chars.peek() == Some(&'"') => {
+                chars.next();
+                cell.push('"');
+            },
+            '"' => in_quotes = !in_quotes,
+            c if c == separator && !in_quotes => {
+                row.push(std::mem::take(&mut cell));
+            },
+            '\r' if !in_quotes => (),
+            '\n' if !in_quotes => {
+                row.push(std::mem::take(&mut cell));
+                rows.push(std::mem::take(&mut row));
+            },
+            c => cell.push(c),
+        }
+    }
+
+    if !cell.is_empty() || !row.is_empty() {
+        row.push(cell);
+        rows.push(row);
+    }
+
+    rows
+}
+
+
+fn parse_datetime(value: &str) -> Option<DateTime<Utc>> {
+    if let Ok(dt) = DateTime::parse_from_rfc3339(value) {
+        return Some(dt.into());
+    }
+
+    // spreadsheets rarely keep the offset, those are taken as UTC
+    ["%Y-%m-%d %H:%M:%S", "%Y-%m-%d %H:%M", "%Y-%m-%dT%H:%M:%S", "%d/%m/%Y %H:%M:%S", "%d/%m/%Y %H:%M"]
+        .iter()
+        .filter_map(|format| NaiveDateTime::parse_from_str(value, format).ok())
+        .next()
+        .map(|naive| Utc.from_utc_datetime(&naive))
+}
+
+
+fn parse_how(value: &str) -> Option<HowItWasStopped> {
+    match value.to_lowercase().as_str() {
+        "crash" | "crashed" | "self-crashed" | "selfcrashed" => Some(HowItWasStopped::SelfCrashed),
+        "kill" | "killed" | "manually-killed" | "manuallykilled" => Some(HowItWasStopped::ManuallyKilled),
+        _ => None,
+    }
+}
+
+
+// built-in names give built-in activities, others are kept as the activities of another setup
+fn parse_what(value: &str) -> Option<WhatWasHappening> {
+    normalize_name(value).map(WhatWasHappening::from)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::attachments::{self, Attachment};
+
+    use super::*;
+
+
+    #[test]
+    fn attachment_names_stay_in_their_folder() {
+        let root = std::env::temp_dir().join(format!("crash-recorder-import-{}", std::process::id()));
+        let export = root.join("export");
+        let data_dir = root.join("data");
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(export.join("attachments").join("r1")).unwrap();
+        AppData::set_data_dir(Some(data_dir.clone()));
+
+        let attachment = |name: &str| Attachment { name: name.into(), size: 1 };
+        let record = Record {
+            id: "r1".into(),
+            attachments: vec![attachment("../../evil.txt"), attachment(".."), attachment("/tmp/evil.txt"), attachment("ok.txt")],
+            ..Record::default()
+        };
+        let data = AppData { records: vec![record], ..AppData::default() };
+        std::fs::write(export.join("records.json"), serde_json::to_string(&data).unwrap()).unwrap();
+        std::fs::write(export.join("attachments").join("r1").join("ok.txt"), "ok").unwrap();
+        // where "attachments/r1/../../evil.txt" leads in the export
+        std::fs::write(export.join("evil.txt"), "evil").unwrap();
+
+        let records = async_std::task::block_on(async {
+            records(preview(export.join("records.json")).await.unwrap()).await.unwrap()
+        });
+
+        assert_eq!(records[0].attachments, vec![attachment("ok.txt")]);
+        assert!(attachments::record_dir("r1").join("ok.txt").exists());
+        assert!(!data_dir.join("evil.txt").exists());
+        assert_eq!(std::fs::read_dir(&data_dir).unwrap().count(), 1);
+        assert_eq!(std::fs::read_dir(data_dir.join("attachments")).unwrap().count(), 1);
+        assert_eq!(std::fs::read_dir(attachments::record_dir("r1")).unwrap().count(), 1);
+
+        let _ = std::fs::remove_dir_all(&root);
+        AppData::set_data_dir(None);
+    }
+
+    #[test]
+    fn csv_cells() {
+        let rows = parse_csv("\u{feff}when,description\r\n\
+                              2020-01-01 10:00,\"quoted, with a comma\"\r\n\
+                              2020-01-02 10:00,\"two\nlines and \"\"quotes\"\"\"\n\
+                              2020-01-03 10:00,");
+
+        assert_eq!(rows, vec![
+            vec!["when".to_string(), "description".into()],
+            vec!["2020-01-01 10:00".into(), "quoted, with a comma".into()],
+            vec!["2020-01-02 10:00".into(), "two\nlines and \"quotes\"".into()],
+            vec!["2020-01-03 10:00".into(), "".into()],
+        ]);
+        assert_eq!(CsvMapping::guess(&rows[0]).get(CsvField::When), Some(0));
+
+        assert_eq!(parse_csv("when;how\n2020-01-01 10:00;killed\n"), vec![
+            vec!["when".to_string(), "how".into()],
+            vec!["2020-01-01 10:00".into(), "killed".into()],
+        ]);
+    }
+
+    #[test]
+    fn csv_dates() {
+        let expected = "2020-05-17T09:41:07Z".parse::<DateTime<Utc>>().unwrap();
+
+        for value in ["2020-05-17T09:41:07Z", "2020-05-17T11:41:07+02:00", "2020-05-17 09:41:07",
+                      "2020-05-17T09:41:07", "17/05/2020 09:41:07"].iter() {
+            assert_eq!(parse_datetime(value), Some(expected), "{}", value);
+        }
+
+        let minutes = "2020-05-17T09:41:00Z".parse::<DateTime<Utc>>().unwrap();
+        assert_eq!(parse_datetime("2020-05-17 09:41"), Some(minutes));
+        assert_eq!(parse_datetime("17/05/2020 09:41"), Some(minutes));
+
+        assert_eq!(parse_datetime("05/17/2020 09:41"), None);
+        assert_eq!(parse_datetime("yesterday"), None);
+    }
+}
@@ -0,0 +1,186 @@
+use std::path::PathBuf;
+use serde_derive::Deserialize;
+use directories::ProjectDirs;
+
+use crate::record::{RecordInput, WhatWasHappening};
+
+
+// One JSON object per line, e.g. {"command": "set-what", "what": "debugging"}
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "command", rename_all = "kebab-case")]
+pub enum IpcCommand {
+    StartFreeze,
+    StartBusy,
+    RecordCrash,
+    RecordKill,
+    SetWhat { what: WhatWasHappening },
+    SetDescription { description: String },
+    QueryStats,
+    AppStarted { application: String },
+    AppStopped { application: String },
+    // a finished record, e.g. from the panic hook of crash-recorder-client
+    Report(RecordInput),
+}
+
+
+// A command waiting for the window to handle it; the client is blocked until `reply`.
+#[derive(Debug, Clone)]
+pub struct IpcRequest {
+    pub command: IpcCommand,
+    reply: std::sync::mpsc::Sender<String>,
+}
+
+
+impl IpcRequest {
+    pub fn reply(&self, response: serde_json::Value) {
+        // the client may be gone already, nothing to do then
+        let _ = self.reply.send(response.to_string());
+    }
+}
+
+
+pub fn socket_path() -> PathBuf {
+    if let Some(path) = std::env::var_os("CRASH_RECORDER_SOCKET") {
+        return path.into();
+    }
+
+    match ProjectDirs::from("rs", "evolix1", "Crash Recorder")
+        .and_then(|project_dirs| project_dirs.runtime_dir().map(PathBuf::from)) {
+        Some(dir) => dir.join("recorder.sock"),
+        None => std::env::temp_dir().join(format!("crash-recorder-{}.sock", crate::host::user_name())),
+    }
+}
+
+
+#[cfg(unix)]
+pub fn listen(path: PathBuf) -> iced::Subscription<IpcRequest> {
+    iced::Subscription::from_recipe(Listener(path))
+}
+
+
+#[cfg(not(unix))]
+pub fn listen(_path: PathBuf) -> iced::Subscription<IpcRequest> {
+    iced::Subscription::none()
+}
+
+
+#[cfg(unix)]
+struct Listener(PathBuf);
+
+
+#[cfg(unix)]
+impl<H, I> iced_native::subscription::Recipe<H, I> for Listener
+where
+    H: std::hash::Hasher,
+{
+    type Output = IpcRequest;
+
+    fn hash(&self, state: &mut H) {
+        use std::hash::Hash;
+
+        std::any::TypeId::of::<Self>().hash(state);
+        self.0.hash(state);
+    }
+
+    fn stream(
+        self: Box<Self>,
+        _input: iced::futures::stream::BoxStream<'static, I>,
+    ) -> iced::futures::stream::BoxStream<'static, Self::Output> {
+        use iced::futures::stream::StreamExt;
+
+        // the socket API is blocking, so it lives in its own threads
+        let (sender, receiver) = iced::futures::channel::mpsc::unbounded();
+        let path = self.0;
+        std::thread::spawn(move || server::run(path, sender));
+
+        receiver.boxed()
+    }
+}
+
+
+#[cfg(unix)]
+mod server {
+    use std::io::{BufRead, BufReader, Write};
+    use std::os::unix::net::{UnixListener, UnixStream};
+    use std::path::PathBuf;
+    use std::time::Duration;
+    use iced::futures::channel::mpsc::UnboundedSender;
+
+    use super::{IpcCommand, IpcRequest};
+
+
+    // long enough for a save, short enough not to hang a shell hook forever
+    const REPLY_TIMEOUT: Duration = Duration::from_secs(5);
+
+
+    pub fn run(path: PathBuf, sender: UnboundedSender<IpcRequest>) {
+        // a live socket belongs to another instance, a dead one is left from a crash
+        if UnixStream::connect(&path).is_ok() {
+            eprintln!("crash-recorder: {} is used by another instance", path.display());
+            return;
+        }
+        let _ = std::fs::remove_file(&path);
+
+        if let Some(dir) = path.parent() {
+            let _ = std::fs::create_dir_all(dir);
+        }
+
+        let listener = match UnixListener::bind(&path) {
+            Ok(listener) => listener,
+            Err(err) => {
+                eprintln!("crash-recorder: cannot listen on {}: {}", path.display(), err);
+                return;
+            }
+        };
+
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let _ = std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600));
+        }
+
+        for stream in listener.incoming().filter_map(|stream| stream.ok()) {
+            let sender = sender.clone();
+            std::thread::spawn(move || serve(stream, sender));
+        }
+    }
+
+    fn serve(stream: UnixStream, sender: UnboundedSender<IpcRequest>) {
+        let mut writer = match stream.try_clone() {
+            Ok(writer) => writer,
+            Err(_) => return,
+        };
+
+        for line in BufReader::new(stream).lines() {
+            let line = match line {
+                Ok(line) => line,
+                Err(_) => return,
+            };
+
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let response = match serde_json::from_str::<IpcCommand>(&line) {
+                Ok(command) => {
+                    let (reply, response) = std::sync::mpsc::channel();
+
+                    if sender.unbounded_send(IpcRequest { command, reply }).is_err() {
+                        return; // window closed
+                    }
+
+                    response.recv_timeout(REPLY_TIMEOUT)
+                        .unwrap_or_else(|_| error("no answer from the recorder"))
+                },
+                Err(err) => error(&err.to_string()),
+            };
+
+            if writeln!(writer, "{}", response).is_err() {
+                return;
+            }
+        }
+    }
+
+    fn error(message: &str) -> String {
+        serde_json::json!({ "ok": false, "error": message }).to_string()
+    }
+}
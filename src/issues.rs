@@ -0,0 +1,117 @@
+use chrono::{DateTime, Utc};
+use serde_derive::{Deserialize, Serialize};
+
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Tracker {
+    Github,
+    Gitlab,
+    Jira,
+    Bugzilla,
+    Other,
+}
+
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum IssueStatus {
+    Open,
+    Fixed,
+    WontFix,
+    Duplicate,
+}
+
+
+// A report to the vendor; records linked to the same tracker and id form a known issue.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct IssueRef {
+    pub tracker: Tracker,
+    pub id: String,
+    #[serde(default)]
+    pub url: String,
+    pub status: IssueStatus,
+}
+
+
+// One known issue and the records linked to it, see `AppData::known_issues`.
+#[derive(Debug, Clone)]
+pub struct KnownIssue {
+    pub issue: IssueRef,
+    pub records: usize,
+    pub last: DateTime<Utc>,
+}
+
+
+impl Tracker {
+    pub const ALL: [Tracker; 5] = [
+        Tracker::Github,
+        Tracker::Gitlab,
+        Tracker::Jira,
+        Tracker::Bugzilla,
+        Tracker::Other,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Tracker::Github => "GitHub",
+            Tracker::Gitlab => "GitLab",
+            Tracker::Jira => "Jira",
+            Tracker::Bugzilla => "Bugzilla",
+            Tracker::Other => tr!("issues.tracker.other"),
+        }
+    }
+
+    pub fn next(self) -> Tracker {
+        let i = Self::ALL.iter().position(|tracker| *tracker == self).unwrap_or(0);
+        Self::ALL[(i + 1) % Self::ALL.len()]
+    }
+}
+
+
+impl Default for Tracker {
+    fn default() -> Self {
+        Tracker::Github
+    }
+}
+
+
+impl IssueStatus {
+    pub const ALL: [IssueStatus; 4] = [
+        IssueStatus::Open,
+        IssueStatus::Fixed,
+        IssueStatus::WontFix,
+        IssueStatus::Duplicate,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            IssueStatus::Open => tr!("issues.status.open"),
+            IssueStatus::Fixed => tr!("issues.status.fixed"),
+            IssueStatus::WontFix => tr!("issues.status.wont-fix"),
+            IssueStatus::Duplicate => tr!("issues.status.duplicate"),
+        }
+    }
+
+    pub fn next(self) -> IssueStatus {
+        let i = Self::ALL.iter().position(|status| *status == self).unwrap_or(0);
+        Self::ALL[(i + 1) % Self::ALL.len()]
+    }
+}
+
+
+impl IssueRef {
+    // url and status may differ between copies of the same issue
+    pub fn same_issue(&self, other: &IssueRef) -> bool {
+        self.tracker == other.tracker && self.id == other.id
+    }
+
+    // e.g. "GitHub #123", "Jira PROJ-42"
+    pub fn name(&self) -> String {
+        match self.tracker {
+            Tracker::Github | Tracker::Gitlab if self.id.chars().all(|c| c.is_ascii_digit()) =>
+                format!("{} #{}", self.tracker.label(), self.id),
+            _ => format!("{} {}", self.tracker.label(), self.id),
+        }
+    }
+}
@@ -1,17 +1,53 @@
+#[macro_use] mod i18n;
 mod record;
 mod app_data;
+mod draft;
+mod sessions;
+mod watcher;
+mod spool;
+mod import;
+mod host;
+mod settings;
+mod sync;
+mod attachments;
+mod issues;
+mod stack_trace;
+mod clusters;
+mod export;
+mod snapshot;
+mod stats;
+mod ipc;
+mod http_api;
+mod hooks;
+mod webhooks;
 mod ui;
+mod cli;
 
 use ui::window::MainWindow;
 
 fn main() {
+    // `crash-recorder started <application>`... talks to the running window and exits
+    if let Some(code) = cli::run(std::env::args().skip(1).collect()) {
+        std::process::exit(code);
+    }
+
+    let (settings, unreadable) = settings::Settings::load();
+    // iced can't resize the window later, see `on_top::resize` for switching modes
+    let size =
+        if settings.compact { (ui::style::COMPACT_WIDTH as u32, ui::style::COMPACT_HEIGHT as u32) }
+        else { (ui::style::WINDOW_WIDTH as u32, ui::style::WINDOW_HEIGHT as u32) };
+    app_data::AppData::set_data_dir(settings.data_dir.clone());
+    ui::fonts::load(settings.font_family.as_deref());
+    i18n::set_locale(settings.locale.unwrap_or_else(i18n::detect));
+
     <MainWindow as iced::Application>::run(
         iced::settings::Settings {
             window: iced::window::Settings {
-                size: (ui::style::WINDOW_WIDTH as u32, ui::style::WINDOW_HEIGHT as u32),
+                size,
                 resizable: true,
                 decorations: true,
             },
+            flags: (settings, unreadable),
             ..iced::settings::Settings::default()
         })
 }
@@ -1,8 +1,13 @@
 use chrono::{DateTime, Utc};
 use serde_derive::{Deserialize, Serialize};
 
+use crate::attachments::Attachment;
+use crate::issues::IssueRef;
+use crate::snapshot::SystemSnapshot;
+use crate::stack_trace::StackTrace;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "kebab-case")]
 pub enum HowItWasStopped {
     SelfCrashed,
@@ -10,18 +15,80 @@ pub enum HowItWasStopped {
 }
 
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
-#[serde(rename_all = "kebab-case")]
+// Stored by name, "typing"... as when only the built-in ones existed.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(from = "String", into = "String")]
 pub enum WhatWasHappening {
     Typing,     // not in execution
     Running,    // during execution, user inputs, not halted by debugger
     Testing,    // during execution, automatic inputs, not halted by debugger
     Debugging,  // during execution, halted by debugger
+    // added in the settings ("profiling", "code-review"...), see `Settings::activities`
+    Custom(String),
+}
+
+
+impl HowItWasStopped {
+    // same as the serialized value
+    pub fn name(self) -> &'static str {
+        match self {
+            HowItWasStopped::SelfCrashed => "self-crashed",
+            HowItWasStopped::ManuallyKilled => "manually-killed",
+        }
+    }
+}
+
+
+impl WhatWasHappening {
+    pub const BUILT_IN: [WhatWasHappening; 4] = [
+        WhatWasHappening::Typing,
+        WhatWasHappening::Running,
+        WhatWasHappening::Testing,
+        WhatWasHappening::Debugging,
+    ];
+
+    // same as the serialized value
+    pub fn name(&self) -> &str {
+        match self {
+            WhatWasHappening::Typing => "typing",
+            WhatWasHappening::Running => "running",
+            WhatWasHappening::Testing => "testing",
+            WhatWasHappening::Debugging => "debugging",
+            WhatWasHappening::Custom(name) => name,
+        }
+    }
+}
+
+
+impl From<String> for WhatWasHappening {
+    fn from(name: String) -> Self {
+        WhatWasHappening::BUILT_IN.iter()
+            .find(|what| what.name() == name)
+            .cloned()
+            .unwrap_or(WhatWasHappening::Custom(name))
+    }
+}
+
+
+impl From<WhatWasHappening> for String {
+    fn from(what: WhatWasHappening) -> Self {
+        what.name().to_string()
+    }
 }
 
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Record {
+    // empty until the record is committed (or loaded from an older file)
+    #[serde(default)]
+    pub id: String,
+    #[serde(default)]
+    pub host: String,
+    #[serde(default)]
+    pub user: String,
+    // from a file that didn't say where it was recorded: not ours, nor anyone's
+    #[serde(default)]
+    pub imported: bool,
     #[serde(
         serialize_with="opt_dt_serde::serialize",
         deserialize_with="opt_dt_serde::deserialize")]
@@ -37,20 +104,249 @@ pub struct Record {
         serialize_with="dt_serde::serialize",
         deserialize_with="dt_serde::deserialize")]
     pub when: DateTime<Utc>,
+    #[serde(default)]
+    pub attachments: Vec<Attachment>,
+    #[serde(default)]
+    pub snapshot: Option<SystemSnapshot>,
+    #[serde(default)]
+    pub application: Option<String>,
+    // "SIGSEGV"... when the reporter knows how the process died
+    #[serde(default)]
+    pub signal: Option<String>,
+    #[serde(default)]
+    pub stack_trace: Option<StackTrace>,
+    // free-form, normalized by `normalize_name`
+    #[serde(default)]
+    pub tags: Vec<String>,
+    // reports to upstream trackers, see `AppData::known_issues`
+    #[serde(default)]
+    pub issues: Vec<IssueRef>,
+    // start of the application's session, to know how long it had been running
+    #[serde(
+        default,
+        serialize_with="opt_dt_serde::serialize",
+        deserialize_with="opt_dt_serde::deserialize")]
+    pub session_started: Option<DateTime<Utc>>,
 }
 
 
 impl Default for Record {
     fn default() -> Self {
         Self {
+            id: String::new(),
+            host: String::new(),
+            user: String::new(),
+            imported: false,
             frozen: None,
             busy: None,
             description: String::new(),
             what: None,
             how: HowItWasStopped::SelfCrashed,
             when: Utc::now(),
+            attachments: vec![],
+            snapshot: None,
+            application: None,
+            signal: None,
+            stack_trace: None,
+            session_started: None,
+            tags: vec![],
+            issues: vec![],
+        }
+    }
+}
+
+
+impl Record {
+    // tag with the local machine when unknown (unless imported), then derive an
+    // id unique across machines
+    pub fn stamp_origin(&mut self) {
+        if !self.imported {
+            if self.host.is_empty() {
+                self.host = crate::host::host_name();
+            }
+
+            if self.user.is_empty() {
+                self.user = crate::host::user_name();
+            }
+        }
+
+        if self.id.is_empty() {
+            let origin =
+                if self.has_origin() { format!("{}@{}", self.user, self.host) }
+                else { "imported".into() };
+
+            // times from CSV files stop at the second: the content tells apart
+            // incidents of the same second
+            self.id = format!("{}-{}{:09}-{:08x}",
+                              origin,
+                              self.when.timestamp(),
+                              self.when.timestamp_subsec_nanos(),
+                              self.content_hash());
+        }
+    }
+
+    pub fn origin(&self) -> String {
+        if self.has_origin() {
+            format!("{}@{}", self.user, self.host)
+        }
+        else {
+            tr!("entry.imported").into()
         }
     }
+
+    // from another recorder or a file: other machines' records keep their
+    // origin, the others get none
+    pub fn stamp_imported(&mut self) {
+        self.imported |= !self.has_origin();
+        self.stamp_origin();
+    }
+
+    pub fn drop_unsafe_attachments(&mut self) {
+        self.attachments.retain(Attachment::is_safe);
+    }
+
+    pub fn has_origin(&self) -> bool {
+        !self.host.is_empty() || !self.user.is_empty()
+    }
+
+    // FNV-1a, stable across builds unlike `DefaultHasher`
+    fn content_hash(&self) -> u32 {
+        let content = serde_json::to_string(&(&self.how, &self.what, &self.description)).unwrap_or_default();
+
+        content.bytes().fold(0x811c_9dc5, |hash: u32, byte| (hash ^ byte as u32).wrapping_mul(0x0100_0193))
+    }
+
+    // false when it was there already or normalizes to nothing
+    pub fn add_tag(&mut self, text: &str) -> bool {
+        match normalize_name(text) {
+            Some(tag) if !self.tags.contains(&tag) => {
+                self.tags.push(tag);
+                true
+            },
+            _ => false,
+        }
+    }
+
+    // the signal gdb saw is kept unless the reporter gave one
+    pub fn set_stack_trace(&mut self, trace: StackTrace) {
+        if self.signal.is_none() {
+            self.signal = trace.signal.clone();
+        }
+
+        self.stack_trace = Some(trace);
+    }
+
+    // what tells this crash from others, see `clusters`
+    pub fn signature(&self) -> Option<String> {
+        self.stack_trace.as_ref()
+            .and_then(StackTrace::signature)
+            .or_else(|| self.signal.clone())
+    }
+
+    // false when it was linked already
+    pub fn link_issue(&mut self, issue: IssueRef) -> bool {
+        if self.issues.iter().any(|linked| linked.same_issue(&issue)) {
+            return false;
+        }
+
+        self.issues.push(issue);
+        true
+    }
+
+    // two records describe the same incident (e.g. imported twice)
+    pub fn same_incident(&self, other: &Record) -> bool {
+        self.when == other.when
+            && self.how == other.how
+            && self.description == other.description
+    }
+}
+
+
+// What other tools send: POST (all optional) and PUT (only those given are
+// changed) of the HTTP API, and reports over the socket.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RecordInput {
+    description: Option<String>,
+    how: Option<HowItWasStopped>,
+    what: Option<WhatWasHappening>,
+    #[serde(deserialize_with="opt_dt_serde::deserialize")]
+    when: Option<DateTime<Utc>>,
+    #[serde(deserialize_with="opt_dt_serde::deserialize")]
+    frozen: Option<DateTime<Utc>>,
+    #[serde(deserialize_with="opt_dt_serde::deserialize")]
+    busy: Option<DateTime<Utc>>,
+    application: Option<String>,
+    signal: Option<String>,
+    // as printed by the program or debugger, parsed here
+    stack_trace: Option<String>,
+    // replaces all tags when given
+    tags: Option<Vec<String>>,
+}
+
+
+impl RecordInput {
+    // enough to be a record of its own, not only changes to one
+    pub fn is_record(&self) -> bool {
+        self.description.is_some() || self.how.is_some()
+    }
+
+    // nothing is changed when it fails
+    pub fn apply(self, record: &mut Record) -> Result<(), String> {
+        let stack_trace = match &self.stack_trace {
+            Some(text) => Some(StackTrace::parse(text).map_err(|_| "no frames found in stack_trace".to_string())?),
+            None => None,
+        };
+
+        if let Some(description) = self.description {
+            record.description = description;
+        }
+        if let Some(how) = self.how {
+            record.how = how;
+        }
+        if self.what.is_some() {
+            record.what = self.what;
+        }
+        if let Some(when) = self.when {
+            record.when = when;
+        }
+        if self.frozen.is_some() {
+            record.frozen = self.frozen;
+        }
+        if self.busy.is_some() {
+            record.busy = self.busy;
+        }
+        if let Some(application) = self.application {
+            record.application = Some(application.trim().to_string()).filter(|name| !name.is_empty());
+        }
+        if self.signal.is_some() {
+            record.signal = self.signal;
+        }
+        if let Some(stack_trace) = stack_trace {
+            record.set_stack_trace(stack_trace);
+        }
+        if let Some(tags) = self.tags {
+            record.tags.clear();
+            for tag in tags.iter() {
+                record.add_tag(tag);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+
+// for tags and activity names: "Large File " and "#large-file" are both "large-file"
+pub fn normalize_name(text: &str) -> Option<String> {
+    let name = text.trim()
+        .trim_start_matches('#')
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join("-")
+        .to_lowercase();
+
+    if name.is_empty() { None } else { Some(name) }
 }
 
 
@@ -0,0 +1,51 @@
+use chrono::{DateTime, Duration, Utc};
+use serde_derive::{Deserialize, Serialize};
+
+use crate::record::{dt_serde, opt_dt_serde};
+
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SessionSource {
+    Manual,     // "Started" button
+    Cli,        // `crash-recorder started <app>`, or the socket directly
+    Watcher,    // the process showed up, see `Settings::watched`
+}
+
+
+// One run of an application, from its start to its crash, kill or exit.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Session {
+    pub application: String,
+    pub source: SessionSource,
+    #[serde(
+        serialize_with="dt_serde::serialize",
+        deserialize_with="dt_serde::deserialize")]
+    pub started: DateTime<Utc>,
+    #[serde(
+        serialize_with="opt_dt_serde::serialize",
+        deserialize_with="opt_dt_serde::deserialize")]
+    pub ended: Option<DateTime<Utc>>,
+}
+
+
+impl Session {
+    pub fn is_open(&self) -> bool {
+        self.ended.is_none()
+    }
+
+    // an open session counts up to now
+    pub fn duration(&self, now: DateTime<Utc>) -> Duration {
+        self.ended.unwrap_or(now) - self.started
+    }
+}
+
+
+// A process to look for in /proc, e.g. {"application": "Editor", "process": "editor"}
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WatchedApplication {
+    pub application: String,
+    // as in /proc/<pid>/comm, or the file name of the executable
+    pub process: String,
+}
@@ -0,0 +1,267 @@
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use serde_derive::{Deserialize, Serialize};
+use directories::ProjectDirs;
+
+use crate::app_data::SaveError;
+use crate::i18n::Locale;
+use crate::record::{WhatWasHappening, normalize_name};
+use crate::ui::shortcuts::Action;
+use crate::ui::style::Theme;
+use crate::ui::screen::Screen;
+use crate::hooks::Hook;
+use crate::webhooks::Webhook;
+use crate::sessions::WatchedApplication;
+
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum TimeDisplay {
+    Local,
+    Utc,
+}
+
+
+// An activity offered besides the built-in ones, e.g. {"name": "code-review", "label": "Code review"}
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Activity {
+    // stored in records, see `record::normalize_name`
+    pub name: String,
+    pub label: String,
+}
+
+
+impl Activity {
+    // the name derived from the label, None for a blank label
+    pub fn new(label: &str) -> Option<Activity> {
+        Some(Activity {
+            name: normalize_name(label)?,
+            label: label.trim().to_string(),
+        })
+    }
+}
+
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    // where records.json, attachments... live, the platform data directory if unset
+    pub data_dir: Option<PathBuf>,
+    pub time_display: TimeDisplay,
+    // detected from the environment if unset
+    pub locale: Option<Locale>,
+    pub theme: Theme,
+    // the one shown when the window closed, reopened next time
+    pub screen: Screen,
+    // only the Frozen/Busy toggles and the Crashed/Killed buttons
+    pub compact: bool,
+    // in compact mode, when the window manager allows it
+    pub always_on_top: bool,
+    // a family known to the system ("DejaVu Sans"...), the default sans-serif if unset
+    pub font_family: Option<String>,
+    pub font_size: u16,
+    // offered after the built-in ones
+    pub activities: Vec<Activity>,
+    // activity preselected for a new record
+    pub default_what: Option<WhatWasHappening>,
+    // when off, records are only written with the "Save" button
+    pub autosave: bool,
+    // wait that long after a change before writing, to group successive changes
+    pub autosave_delay_secs: u64,
+    pub tick_interval_ms: u64,
+    // shared folder (synced drive, NFS...) where each machine writes its records
+    pub sync_dir: Option<PathBuf>,
+    // store load, memory and top processes along with each record
+    pub capture_snapshot: bool,
+    // e.g. "crash": "Ctrl+Enter", actions left out keep their default key
+    pub shortcuts: BTreeMap<Action, String>,
+    // local HTTP/JSON API on 127.0.0.1, off unless a port is set
+    pub http_port: Option<u16>,
+    // run after each committed record, see `hooks::Hook`
+    pub hooks: Vec<Hook>,
+    // URLs receiving a JSON POST for each committed record
+    pub webhooks: Vec<Webhook>,
+    // processes whose start and exit open and close a session, see `watcher`
+    pub watched: Vec<WatchedApplication>,
+}
+
+
+// settings.json when it couldn't be read, and the copy of it kept before the
+// defaults are written over it (none if copying failed too)
+#[derive(Debug, Clone)]
+pub struct Unreadable {
+    pub path: PathBuf,
+    pub copy: Option<PathBuf>,
+}
+
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            data_dir: None,
+            time_display: TimeDisplay::Local,
+            locale: None,
+            theme: Theme::Light,
+            screen: Screen::Record,
+            compact: false,
+            always_on_top: true,
+            font_family: None,
+            font_size: 18,
+            activities: vec![],
+            default_what: None,
+            autosave: true,
+            autosave_delay_secs: 0,
+            tick_interval_ms: 1000,
+            sync_dir: None,
+            capture_snapshot: true,
+            shortcuts: BTreeMap::new(),
+            http_port: None,
+            hooks: vec![],
+            webhooks: vec![],
+            watched: vec![],
+        }
+    }
+}
+
+
+impl Settings {
+    fn path() -> PathBuf {
+        let mut path = match ProjectDirs::from("rs", "evolix1", "Crash Recorder") {
+            Some(project_dirs) => project_dirs.config_dir().into(),
+            None => std::env::current_dir().unwrap_or(PathBuf::new())
+        };
+
+        path.push("settings.json");
+
+        path
+    }
+
+    // built-in ones first, each with its label
+    pub fn activities(&self) -> Vec<(WhatWasHappening, &str)> {
+        let built_in = WhatWasHappening::BUILT_IN.iter()
+            .map(|what| (what.clone(), built_in_label(what)));
+        let custom = self.activities.iter()
+            .map(|activity| (WhatWasHappening::from(activity.name.clone()), activity.label.as_str()));
+
+        built_in.chain(custom).collect()
+    }
+
+    // records may come from a machine knowing other activities, shown by name then
+    pub fn activity_label(&self, what: &WhatWasHappening) -> String {
+        match what {
+            WhatWasHappening::Custom(name) => self.activities.iter()
+                .find(|activity| activity.name == *name)
+                .map_or_else(|| name.clone(), |activity| activity.label.clone()),
+            _ => built_in_label(what).into(),
+        }
+    }
+
+    // messages for the settings screen, empty when everything is usable
+    pub fn validate(&self) -> Vec<String> {
+        let mut errors = vec![];
+
+        let dirs = [
+            (tr!("settings.data-dir"), &self.data_dir),
+            (tr!("settings.sync-dir"), &self.sync_dir),
+        ];
+
+        for (name, dir) in dirs.iter() {
+            if let Some(dir) = dir {
+                if !dir.is_absolute() {
+                    errors.push(tr!("settings.error.absolute", name = name));
+                }
+                else if dir.exists() && !dir.is_dir() {
+                    errors.push(tr!("settings.error.not-folder", name = name));
+                }
+            }
+        }
+
+        if self.data_dir.is_some() && self.data_dir == self.sync_dir {
+            errors.push(tr!("settings.error.same-dirs").into());
+        }
+
+        if !(100..=60_000).contains(&self.tick_interval_ms) {
+            errors.push(tr!("settings.error.tick-range", min = 100, max = 60_000));
+        }
+
+        if !(10..=32).contains(&self.font_size) {
+            errors.push(tr!("settings.error.font-size-range", min = 10, max = 32));
+        }
+
+        if self.autosave_delay_secs > 3600 {
+            errors.push(tr!("settings.error.delay-range", max = 3600));
+        }
+
+        if let Some(port) = self.http_port {
+            if port < 1024 {
+                errors.push(tr!("settings.error.port-range", min = 1024));
+            }
+        }
+
+        for (i, activity) in self.activities.iter().enumerate() {
+            let built_in = WhatWasHappening::BUILT_IN.iter().any(|what| what.name() == activity.name);
+            let repeated = self.activities[..i].iter().any(|other| other.name == activity.name);
+
+            if built_in || repeated {
+                errors.push(tr!("settings.error.activity-exists", label = activity.label));
+            }
+        }
+
+        errors
+    }
+
+    // read before the window opens, so a missing or broken file means defaults;
+    // a broken one is copied to settings.json.bad first, and reported
+    pub fn load() -> (Settings, Option<Unreadable>) {
+        let path = Self::path();
+        let contents = match std::fs::read_to_string(&path) {
+            Ok(contents) => contents,
+            Err(_) if !path.exists() => return (Settings::default(), None),
+            Err(_) => String::new(),
+        };
+
+        match serde_json::from_str(&contents) {
+            Ok(settings) => (settings, None),
+            Err(_) => {
+                let copy = path.with_extension("json.bad");
+                let copy = std::fs::copy(&path, &copy).ok().map(|_| copy);
+                (Settings::default(), Some(Unreadable { path, copy }))
+            },
+        }
+    }
+
+    pub async fn save(self) -> Result<(), SaveError> {
+        use async_std::prelude::*;
+
+        let json = serde_json::to_string_pretty(&self)
+            .map_err(|_| SaveError::FormatError)?;
+
+        let path = Self::path();
+
+        if let Some(dir) = path.parent() {
+            async_std::fs::create_dir_all(dir)
+                .await
+                .map_err(|_| SaveError::DirectoryError)?;
+        }
+
+        let mut file = async_std::fs::File::create(path)
+            .await
+            .map_err(|_| SaveError::FileError)?;
+
+        file.write_all(json.as_bytes())
+            .await
+            .map_err(|_| SaveError::WriteError)
+    }
+}
+
+
+// empty for custom activities, their label is in `Settings::activities`
+pub fn built_in_label(what: &WhatWasHappening) -> &'static str {
+    match what {
+        WhatWasHappening::Typing => tr!("what.typing"),
+        WhatWasHappening::Running => tr!("what.running"),
+        WhatWasHappening::Testing => tr!("what.testing"),
+        WhatWasHappening::Debugging => tr!("what.debugging"),
+        WhatWasHappening::Custom(_) => "",
+    }
+}
@@ -0,0 +1,114 @@
+use serde_derive::{Deserialize, Serialize};
+
+
+const TOP_PROCESSES: usize = 5;
+
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProcessMemory {
+    pub pid: u32,
+    pub name: String,
+    pub rss_kib: u64,
+}
+
+
+// State of the machine when a record is committed, read from /proc (Linux only).
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SystemSnapshot {
+    pub load_average: [f32; 3],
+    pub mem_total_kib: u64,
+    pub mem_available_kib: u64,
+    pub swap_total_kib: u64,
+    pub swap_free_kib: u64,
+    pub uptime_secs: u64,
+    pub kernel: String,
+    pub top_processes: Vec<ProcessMemory>,
+}
+
+
+impl SystemSnapshot {
+    // None where /proc is not available; each missing piece is otherwise left at zero
+    pub fn capture() -> Option<SystemSnapshot> {
+        let meminfo = std::fs::read_to_string("/proc/meminfo").ok()?;
+        let meminfo_kib = |key: &str| -> Option<u64> {
+            meminfo.lines()
+                .find(|line| line.starts_with(key) && line[key.len()..].starts_with(':'))
+                .and_then(|line| line[key.len() + 1..].split_whitespace().next())
+                .and_then(|value| value.parse().ok())
+        };
+
+        let mut snapshot = SystemSnapshot {
+            mem_total_kib: meminfo_kib("MemTotal").unwrap_or(0),
+            mem_available_kib: meminfo_kib("MemAvailable")
+                .or_else(|| meminfo_kib("MemFree"))
+                .unwrap_or(0),
+            swap_total_kib: meminfo_kib("SwapTotal").unwrap_or(0),
+            swap_free_kib: meminfo_kib("SwapFree").unwrap_or(0),
+            ..SystemSnapshot::default()
+        };
+
+        if let Ok(loadavg) = std::fs::read_to_string("/proc/loadavg") {
+            for (i, value) in loadavg.split_whitespace().take(3).enumerate() {
+                snapshot.load_average[i] = value.parse().unwrap_or(0.0);
+            }
+        }
+
+        if let Ok(uptime) = std::fs::read_to_string("/proc/uptime") {
+            snapshot.uptime_secs = uptime.split_whitespace()
+                .next()
+                .and_then(|value| value.parse::<f64>().ok())
+                .map_or(0, |secs| secs as u64);
+        }
+
+        if let Ok(kernel) = std::fs::read_to_string("/proc/sys/kernel/osrelease") {
+            snapshot.kernel = kernel.trim().to_string();
+        }
+
+        snapshot.top_processes = top_processes();
+
+        Some(snapshot)
+    }
+}
+
+
+// `SystemSnapshot::capture` reads every process's status, kept off the executor
+// running the window
+pub async fn capture() -> Option<SystemSnapshot> {
+    async_std::task::spawn_blocking(SystemSnapshot::capture).await
+}
+
+
+fn top_processes() -> Vec<ProcessMemory> {
+    let entries = match std::fs::read_dir("/proc") {
+        Ok(entries) => entries,
+        Err(_) => return vec![],
+    };
+
+    let mut processes: Vec<ProcessMemory> = entries
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| entry.file_name().to_str().and_then(|name| name.parse::<u32>().ok()))
+        .filter_map(|pid| {
+            // processes may exit while we iterate, those are just skipped
+            let status = std::fs::read_to_string(format!("/proc/{}/status", pid)).ok()?;
+            let field = |key: &str| status.lines()
+                .find(|line| line.starts_with(key))
+                .map(|line| line[key.len()..].trim().to_string());
+
+            Some(ProcessMemory {
+                pid,
+                name: field("Name:")?,
+                // kernel threads have no VmRSS
+                rss_kib: field("VmRSS:")?
+                    .split_whitespace()
+                    .next()?
+                    .parse()
+                    .ok()?,
+            })
+        })
+        .collect();
+
+    processes.sort_by(|a, b| b.rss_kib.cmp(&a.rss_kib));
+    processes.truncate(TOP_PROCESSES);
+
+    processes
+}
@@ -0,0 +1,183 @@
+use std::path::{Path, PathBuf};
+use chrono::{DateTime, Utc};
+use directories::ProjectDirs;
+use serde_derive::Deserialize;
+
+use crate::record::{Record, RecordInput};
+
+
+// looked into on the first tick after that many seconds, whatever the tick interval
+pub const SCAN_INTERVAL_SECS: i64 = 2;
+
+// a larger file is not a record, even with a stack trace
+pub const MAX_FILE_SIZE: u64 = 2 * 1024 * 1024;
+
+
+// Either a whole record as we write them (an export, a file from another
+// recorder), or only the fields of `RecordInput`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum SpoolEntry {
+    Input(RecordInput),
+    Record(Record),
+}
+
+
+// A file found in the spool directory, and what could be read of it.
+#[derive(Debug, Clone)]
+pub struct SpoolFile {
+    pub path: PathBuf,
+    pub entry: Result<SpoolEntry, String>,
+}
+
+
+// What became of a file, shown in the window until it is closed.
+#[derive(Debug, Clone)]
+pub struct SpoolOutcome {
+    pub file: String,
+    pub when: DateTime<Utc>,
+    // the record id, or why there is none
+    pub result: Result<String, String>,
+    // false when it couldn't be moved out of the spool directory
+    pub filed: bool,
+}
+
+
+impl SpoolFile {
+    pub fn name(&self) -> String {
+        self.path.file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default()
+    }
+}
+
+
+// Where programs that can't reach the socket drop records, one JSON file each
+// (see crash-recorder-client). It doesn't follow `Settings::data_dir`: writers
+// have to find it without reading our settings.
+pub fn dir() -> PathBuf {
+    if let Some(path) = std::env::var_os("CRASH_RECORDER_SPOOL") {
+        return path.into();
+    }
+
+    match ProjectDirs::from("rs", "evolix1", "Crash Recorder") {
+        Some(project_dirs) => project_dirs.data_dir().join("spool"),
+        None => std::env::temp_dir().join(format!("crash-recorder-{}-spool", crate::host::user_name())),
+    }
+}
+
+
+// A record of the local machine from the fields given; whole records are
+// merged as imports instead, see `AppData::merge`.
+pub fn new_record(input: RecordInput, now: DateTime<Utc>) -> Result<Record, String> {
+    if !input.is_record() {
+        return Err("neither description nor how given".into());
+    }
+
+    let mut record = Record { when: now, ..Record::default() };
+    input.apply(&mut record)?;
+    record.stamp_origin();
+
+    Ok(record)
+}
+
+
+// Files being written end in ".tmp" and are renamed when complete, so only
+// ".json" ones are read. They stay in place until `file_away`.
+pub async fn collect() -> Vec<SpoolFile> {
+    use async_std::prelude::*;
+
+    let mut paths = vec![];
+
+    if let Ok(mut entries) = async_std::fs::read_dir(dir()).await {
+        while let Some(Ok(entry)) = entries.next().await {
+            let path: PathBuf = entry.path().into();
+            if path.is_file() && path.extension().map_or(false, |extension| extension == "json") {
+                paths.push(path);
+            }
+        }
+    }
+
+    // oldest first, writers name files after the time
+    paths.sort();
+
+    let mut files = vec![];
+    for path in paths {
+        let entry = read(&path).await;
+        files.push(SpoolFile { path, entry });
+    }
+
+    files
+}
+
+
+async fn read(path: &Path) -> Result<SpoolEntry, String> {
+    let metadata = async_std::fs::metadata(path)
+        .await
+        .map_err(|err| err.to_string())?;
+
+    if metadata.len() > MAX_FILE_SIZE {
+        return Err(format!("larger than {} MiB", MAX_FILE_SIZE / (1024 * 1024)));
+    }
+
+    let contents = async_std::fs::read_to_string(path)
+        .await
+        .map_err(|err| err.to_string())?;
+
+    // untagged, serde would only say that nothing matched
+    serde_json::from_str::<SpoolEntry>(&contents).map_err(|_| {
+        match serde_json::from_str::<RecordInput>(&contents) {
+            Err(err) => err.to_string(),
+            Ok(_) => "not a record".into(),
+        }
+    })
+}
+
+
+// Into processed/ or failed/, the latter with the reason next to the file.
+// A name taken there already (the same file dropped twice) is suffixed.
+pub async fn file_away(files: Vec<(PathBuf, SpoolOutcome)>) -> Vec<SpoolOutcome> {
+    let spool = dir();
+    let mut outcomes = vec![];
+
+    for (path, mut outcome) in files {
+        outcome.filed = move_away(&spool, &path, outcome.result.as_ref().err()).await;
+        outcomes.push(outcome);
+    }
+
+    outcomes
+}
+
+
+async fn move_away(spool: &Path, path: &Path, error: Option<&String>) -> bool {
+    let folder = spool.join(if error.is_none() { "processed" } else { "failed" });
+    if async_std::fs::create_dir_all(&folder).await.is_err() {
+        return false;
+    }
+
+    let name = match path.file_stem() {
+        Some(stem) => stem.to_string_lossy().into_owned(),
+        None => return false,
+    };
+
+    let mut target = folder.join(format!("{}.json", name));
+    let mut n = 2;
+    while target.exists() {
+        target = folder.join(format!("{}-{}.json", name, n));
+        n += 1;
+    }
+
+    if async_std::fs::rename(path, &target).await.is_err() {
+        // another device: copy, then drop the original
+        if async_std::fs::copy(path, &target).await.is_err() {
+            return false;
+        }
+        let _ = async_std::fs::remove_file(path).await;
+    }
+
+    if let Some(error) = error {
+        let _ = async_std::fs::write(target.with_extension("error.txt"), error).await;
+    }
+
+    true
+}
@@ -0,0 +1,497 @@
+use std::path::PathBuf;
+use serde_derive::{Deserialize, Serialize};
+
+
+// a larger file is most likely not a backtrace
+pub const MAX_TRACE_SIZE: u64 = 1024 * 1024;
+
+// frames kept in the signature, after the runtime's own
+const SIGNATURE_FRAMES: usize = 3;
+
+// panic machinery, libc and JVM internals, never where the bug is
+const RUNTIME_PREFIXES: [&str; 14] = [
+    "std::", "core::", "alloc::", "backtrace::", "<std::", "<core::", "<alloc::", "__rust", "__GI_", "__libc_",
+    "java.lang.Thread.", "java.base/", "jdk.internal.", "sun.reflect.",
+];
+const RUNTIME_FUNCTIONS: [&str; 5] = ["rust_begin_unwind", "rust_panic", "raise", "abort", "_start"];
+const PRIMITIVE_TYPES: [&str; 17] = [
+    "bool", "char", "str", "u8", "u16", "u32", "u64", "u128", "usize",
+    "i8", "i16", "i32", "i64", "i128", "isize", "f32", "f64",
+];
+
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum TraceFormat {
+    RustPanic,
+    Gdb,
+    Java,       // Kotlin and the other JVM languages print the same
+}
+
+
+// One line of a backtrace. Unsymbolicated frames only have an address, and
+// the module it belongs to when the debugger knows it.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Frame {
+    pub function: Option<String>,
+    pub file: Option<String>,
+    pub line: Option<u32>,
+    pub address: Option<String>,
+    pub module: Option<String>,
+}
+
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StackTrace {
+    pub format: TraceFormat,
+    // panic message or exception line
+    #[serde(default)]
+    pub message: String,
+    // "java.lang.IllegalStateException"...
+    #[serde(default)]
+    pub exception: Option<String>,
+    // "SIGSEGV"... when gdb tells
+    #[serde(default)]
+    pub signal: Option<String>,
+    // innermost first, as printed; only the outermost exception for the JVM
+    pub frames: Vec<Frame>,
+    // as pasted, for what the parser doesn't keep
+    pub raw: String,
+}
+
+
+#[derive(Debug, Clone)]
+pub enum TraceError {
+    FileError,
+    TooLarge,
+    NoFrames,
+}
+
+
+impl Frame {
+    // "name (file:line)", or whatever is known of it
+    pub fn text(&self) -> String {
+        let function = self.function.clone()
+            .or_else(|| self.address.clone())
+            .unwrap_or_else(|| "??".into());
+
+        let location = match (&self.file, self.line, &self.module) {
+            (Some(file), Some(line), _) => format!("{}:{}", file, line),
+            (Some(file), None, _) => file.clone(),
+            (None, _, Some(module)) => module.clone(),
+            (None, _, None) => return function,
+        };
+
+        format!("{} ({})", function, location)
+    }
+
+    fn is_runtime(&self) -> bool {
+        match &self.function {
+            Some(function) => {
+                // a trait impl goes with its type: "<usize as core::slice::index::SliceIndex<[T]>>::index"
+                // is the runtime's, "<app::Foo as core::ops::drop::Drop>::drop" isn't
+                let runtime = match function.find(" as ") {
+                    Some(i) if function.starts_with('<') => is_runtime_type(&function[1..i]),
+                    _ => RUNTIME_PREFIXES.iter().any(|prefix| function.starts_with(prefix)),
+                };
+
+                runtime || RUNTIME_FUNCTIONS.contains(&function.as_str())
+            },
+            None => false,
+        }
+    }
+}
+
+
+impl StackTrace {
+    pub fn parse(text: &str) -> Result<StackTrace, TraceError> {
+        let format =
+            if text.lines().any(|line| line.trim_start().starts_with("#0 ")) { TraceFormat::Gdb }
+            else if text.contains("panicked at") || text.contains("stack backtrace:") { TraceFormat::RustPanic }
+            else if text.lines().any(|line| line.trim_start().starts_with("at ") && line.contains('(')) { TraceFormat::Java }
+            else { TraceFormat::RustPanic };
+
+        let mut trace = StackTrace {
+            format,
+            message: String::new(),
+            exception: None,
+            signal: None,
+            frames: vec![],
+            raw: text.to_string(),
+        };
+
+        match format {
+            TraceFormat::RustPanic => trace.parse_rust(text),
+            TraceFormat::Gdb => trace.parse_gdb(text),
+            TraceFormat::Java => trace.parse_java(text),
+        }
+
+        if trace.frames.is_empty() { Err(TraceError::NoFrames) } else { Ok(trace) }
+    }
+
+    // The first frames outside the runtime, without what changes between builds
+    // (addresses, Rust symbol hashes) nor the message, which often holds data.
+    pub fn signature(&self) -> Option<String> {
+        // A backtrace taken in a panic hook starts with the hook, then the panic
+        // machinery; all of them end with the runtime starting main.
+        let (start, end) = match self.format {
+            TraceFormat::RustPanic => {
+                let is = |frame: &Frame, pattern: &str| frame.function.as_ref().map_or(false, |function| function.contains(pattern));
+                let start = self.frames.iter()
+                    .rposition(|frame| is(frame, "rust_begin_unwind") || is(frame, "core::panicking::"))
+                    .map_or(0, |i| i + 1);
+                let end = self.frames.iter()
+                    .position(|frame| is(frame, "__rust_begin_short_backtrace"))
+                    .filter(|end| *end >= start)
+                    .unwrap_or_else(|| self.frames.len());
+                (start, end)
+            },
+            _ => (0, self.frames.len()),
+        };
+
+        let frames: Vec<String> = self.frames[start..end].iter()
+            .filter(|frame| !frame.is_runtime())
+            .filter_map(|frame| frame.function.as_ref())
+            .map(|function| strip_hash(function).to_string())
+            .take(SIGNATURE_FRAMES)
+            .collect();
+
+        if frames.is_empty() {
+            return self.exception.clone();
+        }
+
+        let frames = frames.join(" < ");
+        Some(match &self.exception {
+            Some(exception) => format!("{}: {}", exception, frames),
+            None => frames,
+        })
+    }
+
+    //    thread 'main' panicked at 'message', src/main.rs:5:5
+    //    stack backtrace:
+    //       0: rust_begin_unwind
+    //                 at /rustc/.../panicking.rs:645:5
+    //       1:     0x55d0c1b2c1a3 - app::main::h0123456789abcdef
+    fn parse_rust(&mut self, text: &str) {
+        let mut lines = text.lines().map(str::trim).peekable();
+
+        while let Some(line) = lines.next() {
+            if line.starts_with("thread '") && line.contains("panicked at") {
+                let rest = &line[line.find("panicked at").unwrap_or(0) + "panicked at".len()..];
+                self.message = rest.trim().trim_end_matches(':').to_string();
+
+                // since Rust 1.73 the message has its own line(s), up to the backtrace
+                if !self.message.starts_with('\'') {
+                    let mut message = vec![];
+                    while let Some(next) = lines.peek() {
+                        if next.starts_with("stack backtrace") || next.starts_with("note:") {
+                            break;
+                        }
+                        message.push(lines.next().unwrap_or_default());
+                    }
+                    if !message.is_empty() {
+                        self.message = message.join("\n");
+                    }
+                }
+            }
+            else if let Some(rest) = number_prefix(line).and_then(|(_, rest)| rest.strip_prefix(':')) {
+                let rest = rest.trim();
+                let mut frame = Frame::default();
+
+                let function = match rest.find(" - ") {
+                    Some(i) if rest.starts_with("0x") => {
+                        frame.address = Some(rest[..i].to_string());
+                        &rest[i + 3..]
+                    },
+                    _ => rest,
+                };
+
+                if !function.is_empty() && function != "<unknown>" {
+                    frame.function = Some(function.to_string());
+                }
+
+                self.frames.push(frame);
+            }
+            else if let Some(location) = line.strip_prefix("at ") {
+                if let Some(frame) = self.frames.last_mut() {
+                    let (file, line) = split_location(location);
+                    frame.file = Some(file);
+                    frame.line = line;
+                }
+            }
+        }
+    }
+
+    //    Program received signal SIGSEGV, Segmentation fault.
+    //    #0  0x00007ffff7a42428 in __GI_raise (sig=6) at ../sysdeps/unix/sysv/linux/raise.c:54
+    //    #1  0x00007ffff7a4402a in ?? () from /lib/x86_64-linux-gnu/libc.so.6
+    //    #2  main () at test.c:5
+    fn parse_gdb(&mut self, text: &str) {
+        for line in text.lines().map(str::trim) {
+            if let Some(rest) = line.strip_prefix("Program received signal ") {
+                self.message = line.to_string();
+                self.signal = rest.split(|c: char| c == ',' || c.is_whitespace())
+                    .next()
+                    .map(String::from);
+                continue;
+            }
+
+            let rest = match line.strip_prefix('#').and_then(number_prefix) {
+                Some((_, rest)) => rest.trim(),
+                None => continue,
+            };
+
+            let mut frame = Frame::default();
+
+            let rest = match rest.find(" in ") {
+                Some(i) if rest.starts_with("0x") => {
+                    frame.address = Some(rest[..i].to_string());
+                    &rest[i + 4..]
+                },
+                _ => rest,
+            };
+
+            // the arguments may hold anything, the function name ends at the first " ("
+            let (function, tail) = match rest.find(" (") {
+                Some(i) => (&rest[..i], &rest[i..]),
+                None => (rest, ""),
+            };
+
+            if function != "??" {
+                frame.function = Some(function.to_string());
+            }
+
+            if let Some(i) = tail.rfind(" at ") {
+                let (file, line) = split_location(&tail[i + 4..]);
+                frame.file = Some(file);
+                frame.line = line;
+            }
+            else if let Some(i) = tail.rfind(" from ") {
+                frame.module = Some(tail[i + 6..].trim().to_string());
+            }
+
+            self.frames.push(frame);
+        }
+    }
+
+    //    Exception in thread "main" java.lang.IllegalStateException: boom
+    //        at com.example.Foo.bar(Foo.kt:10)
+    //        at java.base/java.lang.Thread.run(Native Method)
+    //    Caused by: ...
+    fn parse_java(&mut self, text: &str) {
+        for line in text.lines().map(str::trim) {
+            if line.is_empty() {
+                continue;
+            }
+
+            if line.starts_with("Caused by:") || line.starts_with("Suppressed:") {
+                break;
+            }
+
+            if let Some(rest) = line.strip_prefix("at ") {
+                let mut frame = Frame::default();
+                let (function, location) = match rest.find('(') {
+                    Some(i) => (&rest[..i], rest[i + 1..].trim_end_matches(')')),
+                    None => (rest, ""),
+                };
+
+                frame.function = Some(function.trim().to_string());
+
+                if location.contains(':') {
+                    let (file, line) = split_location(location);
+                    frame.file = Some(file);
+                    frame.line = line;
+                }
+                else if !location.is_empty() {
+                    // "Native Method", "Unknown Source"
+                    frame.file = Some(location.to_string());
+                }
+
+                self.frames.push(frame);
+            }
+            else if self.frames.is_empty() && self.message.is_empty() {
+                // the thread name is quoted, the exception follows it
+                let line = match line.rfind('"') {
+                    Some(i) if line.starts_with("Exception in thread") => line[i + 1..].trim(),
+                    _ => line,
+                };
+
+                self.message = line.to_string();
+                self.exception = line.split(':')
+                    .next()
+                    .map(str::trim)
+                    .filter(|exception| !exception.is_empty() && !exception.contains(' '))
+                    .map(String::from);
+            }
+        }
+    }
+}
+
+
+pub async fn load(source: PathBuf) -> Result<StackTrace, TraceError> {
+    let metadata = async_std::fs::metadata(&source)
+        .await
+        .map_err(|_| TraceError::FileError)?;
+
+    if !metadata.is_file() {
+        return Err(TraceError::FileError);
+    }
+
+    if metadata.len() > MAX_TRACE_SIZE {
+        return Err(TraceError::TooLarge);
+    }
+
+    let text = async_std::fs::read_to_string(&source)
+        .await
+        .map_err(|_| TraceError::FileError)?;
+
+    StackTrace::parse(&text)
+}
+
+
+// what was pasted in the record's detail, held to the same size as a file
+pub fn pasted(text: &str) -> Result<StackTrace, TraceError> {
+    if text.len() as u64 > MAX_TRACE_SIZE {
+        return Err(TraceError::TooLarge);
+    }
+
+    StackTrace::parse(text)
+}
+
+
+// "12: rest" -> (12, ": rest")
+fn number_prefix(line: &str) -> Option<(usize, &str)> {
+    let end = line.find(|c: char| !c.is_ascii_digit()).unwrap_or_else(|| line.len());
+    let number = line[..end].parse().ok()?;
+
+    Some((number, &line[end..]))
+}
+
+
+// "src/main.rs:5:5" -> ("src/main.rs", Some(5)), the column is dropped
+fn split_location(location: &str) -> (String, Option<u32>) {
+    let location = location.trim();
+    let mut parts = location.rsplitn(3, ':').collect::<Vec<_>>();
+    parts.reverse();
+
+    let numbers: Vec<Option<u32>> = parts.iter().map(|part| part.parse().ok()).collect();
+    match (parts.len(), numbers.as_slice()) {
+        (3, [_, Some(line), Some(_)]) => (parts[0].to_string(), Some(*line)),
+        (3, [_, _, Some(line)]) => (format!("{}:{}", parts[0], parts[1]), Some(*line)),
+        (2, [_, Some(line)]) => (parts[0].to_string(), Some(*line)),
+        _ => (location.to_string(), None),
+    }
+}
+
+
+// primitives, references, slices, tuples and the types of the standard
+// library, whose impls are the library's own or forward to the type's
+fn is_runtime_type(name: &str) -> bool {
+    let name = name.trim_start_matches("dyn ");
+
+    PRIMITIVE_TYPES.contains(&name)
+        || name.starts_with(|c| c == '&' || c == '*' || c == '[' || c == '(')
+        || RUNTIME_PREFIXES.iter().any(|prefix| name.starts_with(prefix))
+}
+
+
+// "app::main::h0123456789abcdef" -> "app::main"
+fn strip_hash(function: &str) -> &str {
+    match function.rfind("::h") {
+        Some(i) if function.len() - i == 19 && function[i + 3..].chars().all(|c| c.is_ascii_hexdigit()) => &function[..i],
+        _ => function,
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+
+    #[test]
+    fn gdb() {
+        let text = "Program received signal SIGSEGV, Segmentation fault.\n\
+                    #0  0x00007ffff7a42428 in __GI_raise (sig=sig@entry=6) at ../sysdeps/unix/sysv/linux/raise.c:54\n\
+                    #1  0x00007ffff7a4402a in ?? () from /lib/x86_64-linux-gnu/libc.so.6\n\
+                    #2  0x0000555555555131 in parse (input=0x0) at src/parse.c:12\n\
+                    #3  main () at src/main.c:5";
+        let trace = StackTrace::parse(text).unwrap();
+
+        assert_eq!(trace.format, TraceFormat::Gdb);
+        assert_eq!(trace.signal.as_deref(), Some("SIGSEGV"));
+        assert_eq!(trace.message, "Program received signal SIGSEGV, Segmentation fault.");
+        assert_eq!(trace.frames.len(), 4);
+        assert_eq!(trace.frames[0].address.as_deref(), Some("0x00007ffff7a42428"));
+        assert_eq!(trace.frames[0].file.as_deref(), Some("../sysdeps/unix/sysv/linux/raise.c"));
+        assert_eq!(trace.frames[1], Frame {
+            address: Some("0x00007ffff7a4402a".into()),
+            module: Some("/lib/x86_64-linux-gnu/libc.so.6".into()),
+            ..Frame::default()
+        });
+        assert_eq!(trace.frames[3].function.as_deref(), Some("main"));
+        assert_eq!(trace.frames[3].line, Some(5));
+        assert_eq!(trace.signature().as_deref(), Some("parse < main"));
+    }
+
+    #[test]
+    fn java() {
+        let text = "Exception in thread \"main\" java.lang.IllegalStateException: boom: 3\n\
+                    \tat com.example.Foo.bar(Foo.kt:10)\n\
+                    \tat com.example.Main.main(Main.java)\n\
+                    \tat java.base/java.lang.Thread.run(Native Method)\n\
+                    Caused by: java.io.IOException: disk\n\
+                    \tat com.example.Disk.read(Disk.java:1)";
+        let trace = StackTrace::parse(text).unwrap();
+
+        assert_eq!(trace.format, TraceFormat::Java);
+        assert_eq!(trace.exception.as_deref(), Some("java.lang.IllegalStateException"));
+        assert_eq!(trace.message, "java.lang.IllegalStateException: boom: 3");
+        assert_eq!(trace.frames.len(), 3);
+        assert_eq!(trace.frames[0].file.as_deref(), Some("Foo.kt"));
+        assert_eq!(trace.frames[0].line, Some(10));
+        assert_eq!(trace.frames[2].file.as_deref(), Some("Native Method"));
+        assert_eq!(trace.signature().as_deref(),
+                   Some("java.lang.IllegalStateException: com.example.Foo.bar < com.example.Main.main"));
+    }
+
+    #[test]
+    fn locations_and_hashes() {
+        assert_eq!(split_location("src/main.rs:5:9"), ("src/main.rs".into(), Some(5)));
+        assert_eq!(split_location("Foo.kt:10"), ("Foo.kt".into(), Some(10)));
+        assert_eq!(split_location("C:\\src\\main.rs:5"), ("C:\\src\\main.rs".into(), Some(5)));
+        assert_eq!(split_location(" Unknown Source "), ("Unknown Source".into(), None));
+
+        assert_eq!(strip_hash("app::main::h0123456789abcdef"), "app::main");
+        assert_eq!(strip_hash("app::main::hello"), "app::main::hello");
+        assert_eq!(strip_hash("app::main::h0123456789abcdeg"), "app::main::h0123456789abcdeg");
+    }
+
+    #[test]
+    fn trait_impls() {
+        let text = "thread 'main' panicked at src/main.rs:3:5:\n\
+                    boom\n\
+                    stack backtrace:\n\
+                    \x20  0: rust_begin_unwind\n\
+                    \x20  1: core::panicking::panic_fmt\n\
+                    \x20  2: <usize as core::slice::index::SliceIndex<[T]>>::index\n\
+                    \x20  3: <app::Guard as core::ops::drop::Drop>::drop\n\
+                    \x20  4: core::ptr::drop_in_place<app::Guard>\n\
+                    \x20  5: <&app::Guard as core::fmt::Debug>::fmt\n\
+                    \x20  6: app::main";
+        let trace = StackTrace::parse(text).unwrap();
+
+        assert_eq!(trace.signature().as_deref(), Some("<app::Guard as core::ops::drop::Drop>::drop < app::main"));
+    }
+
+    #[test]
+    fn message_lines_up_to_backtrace() {
+        let text = "thread 'main' panicked at src/main.rs:9:5:\nsync failed\n3 retries failed\nstack backtrace:\n   0: rust_begin_unwind\n   1: app::sync\n   2: app::main";
+        let trace = StackTrace::parse(text).unwrap();
+
+        assert_eq!(trace.message, "sync failed\n3 retries failed");
+        assert_eq!(trace.frames.len(), 3);
+        assert_eq!(trace.signature().as_deref(), Some("app::sync < app::main"));
+    }
+}
@@ -0,0 +1,132 @@
+use std::collections::BTreeMap;
+use chrono::{DateTime, Duration, Utc};
+use serde_derive::Serialize;
+
+use crate::record::{Record, HowItWasStopped, opt_dt_serde};
+use crate::sessions::Session;
+use crate::issues::{IssueRef, IssueStatus};
+
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Stats {
+    pub total: usize,
+    pub crashed: usize,
+    pub killed: usize,
+    // linked to at least one upstream issue or not
+    pub reported: usize,
+    pub unreported: usize,
+    // distinct issues, and those still open
+    pub issues: usize,
+    pub open_issues: usize,
+    pub last_24h: usize,
+    pub last_7d: usize,
+    #[serde(serialize_with="opt_dt_serde::serialize")]
+    pub last: Option<DateTime<Utc>>,
+    // activity name ("unspecified" when none was picked) -> count
+    pub by_what: BTreeMap<String, usize>,
+    // a record with several tags counts once for each
+    pub by_tag: BTreeMap<String, usize>,
+    // time the applications were known to run, summed over all sessions
+    pub use_secs: i64,
+    // None until something has been running
+    pub crashes_per_hour: Option<f64>,
+    pub by_application: BTreeMap<String, ApplicationStats>,
+}
+
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ApplicationStats {
+    pub sessions: usize,
+    pub records: usize,
+    pub use_secs: i64,
+    pub crashes_per_hour: Option<f64>,
+    // mean time from start to a record, over records with a known session
+    pub mean_uptime_secs: Option<i64>,
+}
+
+
+fn per_hour(count: usize, secs: i64) -> Option<f64> {
+    if secs > 0 { Some(count as f64 * 3600.0 / secs as f64) } else { None }
+}
+
+
+impl Stats {
+    pub fn compute(records: &[Record], sessions: &[Session], now: DateTime<Utc>) -> Stats {
+        let mut stats = Stats::default();
+        let mut uptimes: BTreeMap<String, (i64, i64)> = BTreeMap::new();
+        let mut issues: Vec<&IssueRef> = vec![];
+
+        for session in sessions {
+            let secs = session.duration(now).num_seconds().max(0);
+            let application = stats.by_application.entry(session.application.clone()).or_default();
+            application.sessions += 1;
+            application.use_secs += secs;
+            stats.use_secs += secs;
+        }
+
+        for record in records {
+            stats.total += 1;
+
+            match record.how {
+                HowItWasStopped::SelfCrashed => stats.crashed += 1,
+                HowItWasStopped::ManuallyKilled => stats.killed += 1,
+            }
+
+            if record.issues.is_empty() {
+                stats.unreported += 1;
+            }
+            else {
+                stats.reported += 1;
+            }
+
+            for issue in record.issues.iter() {
+                if !issues.iter().any(|known| known.same_issue(issue)) {
+                    issues.push(issue);
+                }
+            }
+
+            if now - record.when <= Duration::hours(24) {
+                stats.last_24h += 1;
+            }
+
+            if now - record.when <= Duration::days(7) {
+                stats.last_7d += 1;
+            }
+
+            if stats.last.map_or(true, |last| last < record.when) {
+                stats.last = Some(record.when);
+            }
+
+            let what = record.what.as_ref().map_or("unspecified", |what| what.name());
+            *stats.by_what.entry(what.into()).or_insert(0) += 1;
+
+            for tag in record.tags.iter() {
+                *stats.by_tag.entry(tag.clone()).or_insert(0) += 1;
+            }
+
+            if let Some(name) = &record.application {
+                stats.by_application.entry(name.clone()).or_default().records += 1;
+
+                if let Some(started) = record.session_started {
+                    let uptime = uptimes.entry(name.clone()).or_insert((0, 0));
+                    uptime.0 += (record.when - started).num_seconds().max(0);
+                    uptime.1 += 1;
+                }
+            }
+        }
+
+        for (name, application) in stats.by_application.iter_mut() {
+            application.crashes_per_hour = per_hour(application.records, application.use_secs);
+            application.mean_uptime_secs = uptimes.get(name).map(|(secs, count)| secs / count);
+        }
+
+        stats.issues = issues.len();
+        stats.open_issues = issues.iter().filter(|issue| issue.status == IssueStatus::Open).count();
+
+        // only records tied to an application happened during the measured time
+        let attributed = stats.by_application.values().map(|application| application.records).sum();
+        stats.crashes_per_hour = per_hour(attributed, stats.use_secs);
+
+        stats
+    }
+}
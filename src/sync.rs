@@ -0,0 +1,113 @@
+use std::path::{Path, PathBuf};
+use chrono::{DateTime, Utc};
+use serde_derive::{Deserialize, Serialize};
+
+use crate::app_data::{AppData, SaveError, safe_file_name};
+use crate::record::{Record, dt_serde};
+
+
+// Content of the file each machine owns in the shared folder.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct MachineRecords {
+    host: String,
+    user: String,
+    #[serde(
+        serialize_with="dt_serde::serialize",
+        deserialize_with="dt_serde::deserialize")]
+    updated: DateTime<Utc>,
+    records: Vec<Record>,
+}
+
+
+fn file_path(dir: &Path, user: &str, host: &str) -> PathBuf {
+    dir.join(format!("records-{}.json", safe_file_name(&format!("{}@{}", user, host))))
+}
+
+
+// Merge the records of every machine found in the shared folder. A machine is
+// authoritative for the records it originated, so its version replaces ours;
+// the others it carries (a stale or copied file) are only added when unknown.
+pub async fn pull(dir: &Path, data: &mut AppData) {
+    use async_std::prelude::*;
+
+    let mut entries = match async_std::fs::read_dir(dir).await {
+        Ok(entries) => entries,
+        Err(_) => return,
+    };
+
+    while let Some(Ok(entry)) = entries.next().await {
+        let path: PathBuf = entry.path().into();
+        let is_machine_file = path.file_name()
+            .and_then(|name| name.to_str())
+            .map_or(false, |name| name.starts_with("records-") && name.ends_with(".json"));
+
+        if !is_machine_file {
+            continue;
+        }
+
+        // a file being written by its owner right now is simply picked up next time
+        let machine: MachineRecords = match async_std::fs::read_to_string(&path).await
+            .ok()
+            .and_then(|contents| serde_json::from_str(&contents).ok()) {
+            Some(machine) => machine,
+            None => continue,
+        };
+
+        // a file under another machine's name speaks for none
+        let owned_file = file_path(dir, &machine.user, &machine.host) == path;
+
+        for mut record in machine.records {
+            if record.host.is_empty() && record.user.is_empty() {
+                record.host = machine.host.clone();
+                record.user = machine.user.clone();
+            }
+            record.stamp_origin();
+            record.drop_unsafe_attachments();
+
+            let owned = owned_file && record.host == machine.host && record.user == machine.user;
+            match data.records.iter_mut().find(|existing| existing.id == record.id) {
+                Some(existing) if owned => *existing = record,
+                Some(_) => (),
+                None => data.records.push(record),
+            }
+        }
+    }
+
+    data.records.sort_by_key(|record| record.when);
+}
+
+
+// Publish the records originated on this machine.
+pub async fn push(dir: &Path, data: &AppData) -> Result<(), SaveError> {
+    let host = crate::host::host_name();
+    let user = crate::host::user_name();
+
+    let machine = MachineRecords {
+        records: data.records.iter()
+            .filter(|record| record.host == host && record.user == user)
+            .cloned()
+            .collect(),
+        host,
+        user,
+        updated: Utc::now(),
+    };
+
+    let json = serde_json::to_string_pretty(&machine)
+        .map_err(|_| SaveError::FormatError)?;
+
+    async_std::fs::create_dir_all(dir)
+        .await
+        .map_err(|_| SaveError::DirectoryError)?;
+
+    // write aside then rename, so other machines never read a truncated file
+    let path = file_path(dir, &machine.user, &machine.host);
+    let tmp_path = path.with_extension("json.tmp");
+
+    async_std::fs::write(&tmp_path, json)
+        .await
+        .map_err(|_| SaveError::WriteError)?;
+
+    async_std::fs::rename(&tmp_path, &path)
+        .await
+        .map_err(|_| SaveError::FileError)
+}
@@ -1,6 +1,6 @@
 use iced::{
     button, text_input, scrollable,
-    Length, HorizontalAlignment, Align, Color,
+    Length, HorizontalAlignment, Align,
     Container, Column, Row, Scrollable, 
     Text, TextInput, Button, Space, Checkbox, Radio,
 };
@@ -17,11 +17,28 @@ pub enum ColumnAlignment {
 }
 
 
-pub struct UiBuilder;
+pub struct UiBuilder {
+    palette: style::Palette,
+    font_size: u16,
+}
 
 impl UiBuilder {
-    pub fn new() -> UiBuilder {
-        UiBuilder {}
+    pub fn new(theme: style::Theme, font_size: u16) -> UiBuilder {
+        UiBuilder {
+            palette: theme.palette(),
+            font_size,
+        }
+    }
+
+    fn text_size(&self, size: u16) -> u16 {
+        (size as u32 * self.font_size as u32 / style::BASE_FONT_SIZE as u32) as u16
+    }
+
+    fn themed(&self, btn_style: style::ButtonStyle) -> style::Themed {
+        style::Themed {
+            style: btn_style,
+            palette: self.palette,
+        }
     }
 
     pub fn root<'a>(&self, debug: bool,
@@ -34,12 +51,36 @@ impl UiBuilder {
             .into();
 
         if debug {
-            central = central.explain(Color::BLACK)
+            central = central.explain(self.palette.text)
         }
 
         Container::new(central)
             .width(Length::Fill) // fill the window width
+            .height(Length::Fill) // so the background covers it all
             .center_x()
+            .style(style::Window(self.palette))
+            .into()
+    }
+
+    // the compact window: one row across the whole width
+    pub fn strip<'a>(&self, debug: bool,
+                     items: Vec<UiElement!(for<'a>)>) -> UiElement!(for<'a>) {
+        let mut central: UiElement!() =
+            Row::with_children(items)
+            .padding(10)
+            .align_items(Align::Center) // vertical align
+            .width(Length::Fill)
+            .into();
+
+        if debug {
+            central = central.explain(self.palette.text)
+        }
+
+        Container::new(central)
+            .width(Length::Fill)
+            .height(Length::Fill)
+            .center_y()
+            .style(style::Window(self.palette))
             .into()
     }
 
@@ -108,6 +149,46 @@ impl UiBuilder {
             .into()
     }
 
+    // a filling element (typically an input) followed by its actions
+    pub fn fill_row<'a>(&self,
+                        main: UiElement!(for<'a>),
+                        actions: Vec<UiElement!(for<'a>)>) -> UiElement!(for<'a>)
+    {
+        let mut row = Row::new()
+            .push(main)
+            .width(Length::Fill)
+            .align_items(Align::Center);
+
+        for action in actions {
+            row = row.push(self.action_hspacer()).push(action);
+        }
+
+        row.into()
+    }
+
+    // one button per screen, the current one highlighted
+    pub fn tabs<'a>(&self,
+                    tabs: Vec<(&'a mut button::State, &str, bool, UiMessage!())>) -> UiElement!(for<'a>)
+    {
+        let mut row = Row::new()
+            .width(Length::Fill)
+            .spacing(style::LIST_GAP)
+            .align_items(Align::Center);
+
+        for (state, text, selected, msg) in tabs {
+            let btn_style =
+                if selected { style::ButtonStyle::Primary }
+                else { style::ButtonStyle::Flat };
+
+            row = row.push(Button::new(state, Text::new(text).size(self.text_size(14)))
+                .padding(6)
+                .on_press(msg)
+                .style(self.themed(btn_style)));
+        }
+
+        row.into()
+    }
+
     pub fn list<'a>(&self,
                     state: &'a mut scrollable::State,
                     items: Vec<UiElement!(for<'a>)>) -> UiElement!(for<'a>)
@@ -119,6 +200,7 @@ impl UiBuilder {
         Scrollable::new(state)
             .width(Length::Fill)
             .padding(5)
+            .style(style::List(self.palette))
             .push(central)
             .into()
     }
@@ -130,8 +212,8 @@ impl UiBuilder {
             .horizontal_alignment(HorizontalAlignment::Center)
             .width(Length::Fill)
             .font(style::FontStyle::Bold.into())
-            .size(24)
-            .color([0.1, 0.1, 0.1])
+            .size(self.text_size(24))
+            .color(self.palette.title)
             .into()
     }
 
@@ -142,8 +224,8 @@ impl UiBuilder {
             .horizontal_alignment(HorizontalAlignment::Center)
             .width(Length::Fill)
             .font(style::FontStyle::Italic.into())
-            .size(18)
-            .color([0.1, 0.1, 0.3])
+            .size(self.text_size(18))
+            .color(self.palette.placeholder)
             .into()
     }
 
@@ -152,8 +234,8 @@ impl UiBuilder {
     {
         Text::new(label)
             .font(style::FontStyle::Regular.into())
-            .size(18) // font size
-            .color([0.2, 0.2, 0.2]) // font color
+            .size(self.text_size(18)) // font size
+            .color(self.palette.text) // font color
             .into()
     }
 
@@ -163,9 +245,10 @@ impl UiBuilder {
                      value: &'a str,
                      msg: fn(String) -> UiMessage!()) -> UiElement!(for<'a>) {
         TextInput::new(state, placeholder, value, msg)
-            .size(16) // font size
+            .size(self.text_size(16)) // font size
             .padding(5)
             .width(Length::Fill)
+            .style(style::Input(self.palette))
             .into()
     }
 
@@ -174,12 +257,67 @@ impl UiBuilder {
                       text: &str,
                       btn_style: style::ButtonStyle,
                       msg: UiMessage!()) -> UiElement!(for<'a>) {
-        Button::new(state, Text::new(text))
+        Button::new(state, Text::new(text).size(self.text_size(20)))
             .min_width(50)
             .min_height(20)
             .padding(10)
             .on_press(msg)
-            .style(btn_style)
+            .style(self.themed(btn_style))
+            .into()
+    }
+
+    pub fn list_item<'a>(&self, state: &'a mut button::State,
+                         content: UiElement!(for<'a>),
+                         msg: UiMessage!()) -> UiElement!(for<'a>) {
+        Button::new(state, content)
+            .width(Length::Fill)
+            .padding(2)
+            .on_press(msg)
+            .style(self.themed(style::ButtonStyle::Flat))
+            .into()
+    }
+
+    // a small button for tags and filters, highlighted when selected
+    pub fn chip<'a>(&self, state: &'a mut button::State,
+                    text: &str,
+                    selected: bool,
+                    msg: UiMessage!()) -> UiElement!(for<'a>) {
+        let btn_style =
+            if selected { style::ButtonStyle::Primary }
+            else { style::ButtonStyle::Secondary };
+
+        Button::new(state, Text::new(text).size(self.text_size(14)))
+            .padding(4)
+            .on_press(msg)
+            .style(self.themed(btn_style))
+            .into()
+    }
+
+    pub fn chips<'a>(&self, chips: Vec<UiElement!(for<'a>)>) -> UiElement!(for<'a>) {
+        Row::with_children(chips)
+            .spacing(style::LIST_GAP)
+            .align_items(Align::Center)
+            .into()
+    }
+
+    // `main` with the tags it carries below, as read-only chips
+    pub fn tagged<'a>(&self, main: UiElement!(for<'a>), tags: &[String]) -> UiElement!(for<'a>) {
+        if tags.is_empty() {
+            return main;
+        }
+
+        let mut row = Row::new().spacing(style::LIST_GAP);
+
+        for tag in tags {
+            row = row.push(Container::new(Text::new(tag.as_str()).size(self.text_size(14)))
+                .padding(2)
+                .style(style::Chip(self.palette)));
+        }
+
+        Column::new()
+            .spacing(style::LIST_GAP)
+            .push(main)
+            .push(row)
             .into()
     }
 
@@ -190,8 +328,8 @@ impl UiBuilder {
                         msg: fn(bool) -> UiMessage!()) -> UiElement!(for<'a>) {
         Checkbox::new(state, label, msg)
             .spacing(8)
-            .text_size(18)
-            .style(btn_style)
+            .text_size(self.text_size(18))
+            .style(self.themed(btn_style))
             .into()
     }
 
@@ -206,7 +344,7 @@ impl UiBuilder {
         Radio::new(value, label, current, msg)
             //.spacing(8)
             //.text_size(18)
-            .style(btn_style)
+            .style(self.themed(btn_style))
             .into()
     }
 
@@ -218,6 +356,10 @@ impl UiBuilder {
         Space::new(Length::Shrink, Length::Units(style::ITEM_GAP)).into()
     }
 
+    pub fn list_vspacer(&self) -> UiElement!(for<'static>) {
+        Space::new(Length::Shrink, Length::Units(style::LIST_GAP)).into()
+    }
+
     pub fn action_hspacer(&self) -> UiElement!(for<'static>) {
         Space::new(Length::Units(style::BUTTON_GAP), Length::Shrink).into()
     }
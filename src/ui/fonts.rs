@@ -0,0 +1,103 @@
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+use iced::Font;
+
+
+// in the order of `style::FontStyle`
+const STYLES: [&str; 4] = ["Regular", "Italic", "Bold", "Bold Italic"];
+
+
+// iced's own bundled font until `load` finds better
+static FONTS: RwLock<[Font; 4]> = RwLock::new([Font::Default; 4]);
+
+
+// Each style is looked up in the configured family, then in the system
+// sans-serif font, and falls back to the font bundled with iced.
+pub fn load(family: Option<&str>) {
+    let mut fonts = [Font::Default; 4];
+
+    for (font, style) in fonts.iter_mut().zip(STYLES.iter()) {
+        let found = family
+            .filter(|family| !family.trim().is_empty())
+            .and_then(|family| find(family.trim(), style, true))
+            .or_else(|| find(SYSTEM_FAMILY, style, false))
+            .and_then(|path| read(&path));
+
+        if let Some(found) = found {
+            *font = found;
+        }
+    }
+
+    if let Ok(mut current) = FONTS.write() {
+        *current = fonts;
+    }
+}
+
+
+pub fn get(index: usize) -> Font {
+    FONTS.read().map_or(Font::Default, |fonts| fonts[index])
+}
+
+
+fn read(path: &Path) -> Option<Font> {
+    let bytes = std::fs::read(path).ok()?;
+
+    // iced wants them for the whole run; only a font change leaks another set
+    Some(Font::External {
+        name: Box::leak(path.display().to_string().into_boxed_str()),
+        bytes: Box::leak(bytes.into_boxed_slice()),
+    })
+}
+
+
+#[cfg(not(target_os="windows"))]
+const SYSTEM_FAMILY: &str = "sans-serif";
+
+
+#[cfg(not(target_os="windows"))]
+fn find(family: &str, style: &str, exact: bool) -> Option<PathBuf> {
+    let output = std::process::Command::new("fc-match")
+        .arg("--format=%{family}\n%{file}")
+        .arg(format!("{}:style={}", family, style))
+        .output()
+        .ok()?;
+
+    let text = String::from_utf8(output.stdout).ok()?;
+    let mut lines = text.lines();
+    let matched = lines.next()?;
+    let path = PathBuf::from(lines.next()?);
+
+    // fontconfig always answers, with a substitute for an unknown family
+    if exact && !matched.split(',').any(|name| name.trim().eq_ignore_ascii_case(family)) {
+        return None;
+    }
+
+    // collections and bitmap formats are not understood by the renderer
+    match path.extension().and_then(|ext| ext.to_str()).map(|ext| ext.to_lowercase()) {
+        Some(ref ext) if ext == "ttf" || ext == "otf" => Some(path),
+        _ => None,
+    }
+}
+
+
+#[cfg(target_os="windows")]
+const SYSTEM_FAMILY: &str = "Arial";
+
+
+// no fontconfig here, files follow the "arial.ttf", "arialbd.ttf"... naming
+#[cfg(target_os="windows")]
+fn find(family: &str, style: &str, _exact: bool) -> Option<PathBuf> {
+    let dir = PathBuf::from(std::env::var_os("WINDIR").unwrap_or_else(|| "C:\\Windows".into()))
+        .join("Fonts");
+    let base = family.replace(' ', "").to_lowercase();
+    let suffixes: &[&str] = match style {
+        "Regular" => &["", "r"],
+        "Italic" => &["i"],
+        "Bold" => &["bd", "b"],
+        _ => &["bi", "z"],
+    };
+
+    suffixes.iter()
+        .map(|suffix| dir.join(format!("{}{}.ttf", base, suffix)))
+        .find(|path| path.is_file())
+}
@@ -1,4 +1,8 @@
 #[macro_use] pub mod utils;
 pub mod style;
+pub mod fonts;
 pub mod builder;
+pub mod shortcuts;
+pub mod screen;
+pub mod on_top;
 pub mod window;
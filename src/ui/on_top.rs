@@ -0,0 +1,40 @@
+// iced doesn't expose these, so the window manager is asked through wmctrl
+// (X11, XWayland); without it the window just stays as it is.
+#[cfg(unix)]
+pub async fn set_always_on_top(title: String, on_top: bool) {
+    let _ = async_std::task::spawn_blocking(move || {
+        std::process::Command::new("wmctrl")
+            .args(&["-F", "-r", &title, "-b", if on_top { "add,above" } else { "remove,above" }])
+            .stdout(std::process::Stdio::null())
+            .stderr(std::process::Stdio::null())
+            .status()
+    }).await;
+}
+
+
+#[cfg(not(unix))]
+pub async fn set_always_on_top(_title: String, _on_top: bool) {
+}
+
+
+// Between the full window and the compact strip. A maximized window is
+// restored first, the window manager would ignore the size otherwise.
+#[cfg(unix)]
+pub async fn resize(title: String, width: u16, height: u16) {
+    let _ = async_std::task::spawn_blocking(move || {
+        let wmctrl = |args: &[&str]| std::process::Command::new("wmctrl")
+            .args(&["-F", "-r", &title])
+            .args(args)
+            .stdout(std::process::Stdio::null())
+            .stderr(std::process::Stdio::null())
+            .status();
+
+        let _ = wmctrl(&["-b", "remove,maximized_vert,maximized_horz"]);
+        wmctrl(&["-e", &format!("0,-1,-1,{},{}", width, height)])
+    }).await;
+}
+
+
+#[cfg(not(unix))]
+pub async fn resize(_title: String, _width: u16, _height: u16) {
+}
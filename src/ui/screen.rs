@@ -0,0 +1,37 @@
+use serde_derive::{Deserialize, Serialize};
+
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Screen {
+    Record,
+    History,
+    Signatures,
+    Statistics,
+    Applications,
+    Settings,
+}
+
+
+impl Screen {
+    // in the order of the tabs
+    pub const ALL: [Screen; 6] = [
+        Screen::Record,
+        Screen::History,
+        Screen::Signatures,
+        Screen::Statistics,
+        Screen::Applications,
+        Screen::Settings,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Screen::Record => tr!("screen.record"),
+            Screen::History => tr!("screen.history"),
+            Screen::Signatures => tr!("screen.signatures"),
+            Screen::Statistics => tr!("screen.statistics"),
+            Screen::Applications => tr!("screen.applications"),
+            Screen::Settings => tr!("screen.settings"),
+        }
+    }
+}
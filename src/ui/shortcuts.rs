@@ -0,0 +1,259 @@
+use std::collections::BTreeMap;
+use serde_derive::{Deserialize, Serialize};
+use iced_native::input::keyboard::{KeyCode, ModifiersState};
+
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Action {
+    Crash,
+    Kill,
+    ToggleFrozen,
+    ToggleBusy,
+    Typing,
+    Running,
+    Testing,
+    Debugging,
+    FocusSearch,
+    Back,
+    ToggleCheatSheet,
+    ToggleLayoutDebug,
+    ToggleCompact,
+}
+
+
+impl Action {
+    pub const ALL: [Action; 13] = [
+        Action::Crash,
+        Action::Kill,
+        Action::ToggleFrozen,
+        Action::ToggleBusy,
+        Action::Typing,
+        Action::Running,
+        Action::Testing,
+        Action::Debugging,
+        Action::FocusSearch,
+        Action::Back,
+        Action::ToggleCheatSheet,
+        Action::ToggleLayoutDebug,
+        Action::ToggleCompact,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Action::Crash => tr!("action.crash"),
+            Action::Kill => tr!("action.kill"),
+            Action::ToggleFrozen => tr!("action.toggle-frozen"),
+            Action::ToggleBusy => tr!("action.toggle-busy"),
+            Action::Typing => tr!("what.typing"),
+            Action::Running => tr!("what.running"),
+            Action::Testing => tr!("what.testing"),
+            Action::Debugging => tr!("what.debugging"),
+            Action::FocusSearch => tr!("action.focus-search"),
+            Action::Back => tr!("common.back"),
+            Action::ToggleCheatSheet => tr!("action.toggle-cheat-sheet"),
+            Action::ToggleLayoutDebug => tr!("action.toggle-layout-debug"),
+            Action::ToggleCompact => tr!("action.toggle-compact"),
+        }
+    }
+
+    fn default_binding(self) -> &'static str {
+        match self {
+            Action::Crash => "Ctrl+Enter",
+            Action::Kill => "Ctrl+K",
+            Action::ToggleFrozen => "F",
+            Action::ToggleBusy => "B",
+            Action::Typing => "1",
+            Action::Running => "2",
+            Action::Testing => "3",
+            Action::Debugging => "4",
+            Action::FocusSearch => "Ctrl+F",
+            Action::Back => "Escape",
+            Action::ToggleCheatSheet => "F1",
+            Action::ToggleLayoutDebug => "F11",
+            Action::ToggleCompact => "F10",
+        }
+    }
+}
+
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct KeyBinding {
+    key: KeyCode,
+    control: bool,
+    shift: bool,
+    alt: bool,
+    logo: bool,
+    // no modifier and the key produces a character: must not fire while typing
+    plain: bool,
+    label: String,
+}
+
+
+impl KeyBinding {
+    // "Ctrl+Shift+K", "F", "F11"... (case insensitive)
+    pub fn parse(text: &str) -> Option<KeyBinding> {
+        let mut parts: Vec<&str> = text.split('+').map(|part| part.trim()).collect();
+        let key_name = parts.pop().filter(|name| !name.is_empty())?;
+        let (key, printable) = key_code(key_name)?;
+
+        let mut binding = KeyBinding {
+            key,
+            control: false,
+            shift: false,
+            alt: false,
+            logo: false,
+            plain: false,
+            label: String::new(),
+        };
+
+        for modifier in parts {
+            match modifier.to_lowercase().as_str() {
+                "ctrl" | "control" => binding.control = true,
+                "shift" => binding.shift = true,
+                "alt" => binding.alt = true,
+                "super" | "logo" | "meta" | "cmd" => binding.logo = true,
+                _ => return None,
+            }
+        }
+
+        binding.plain = printable && !(binding.control || binding.alt || binding.logo);
+        binding.label = [
+            (binding.control, "Ctrl+"),
+            (binding.shift, "Shift+"),
+            (binding.alt, "Alt+"),
+            (binding.logo, "Super+"),
+        ].iter()
+            .filter(|(set, _)| *set)
+            .map(|(_, name)| *name)
+            .collect::<String>() + &capitalized(key_name);
+
+        Some(binding)
+    }
+
+    fn matches(&self, key: KeyCode, modifiers: ModifiersState) -> bool {
+        self.key == key
+            && self.control == modifiers.control
+            && self.shift == modifiers.shift
+            && self.alt == modifiers.alt
+            && self.logo == modifiers.logo
+    }
+
+    pub fn label(&self) -> &str {
+        &self.label
+    }
+}
+
+
+pub struct Shortcuts {
+    bindings: Vec<(KeyBinding, Action)>,
+}
+
+
+impl Default for Shortcuts {
+    fn default() -> Self {
+        Shortcuts::new(&BTreeMap::new())
+    }
+}
+
+
+impl Shortcuts {
+    // bindings from the settings file win, the defaults fill the rest;
+    // an entry that doesn't parse keeps the default binding
+    pub fn new(overrides: &BTreeMap<Action, String>) -> Shortcuts {
+        let bindings = Action::ALL.iter()
+            .filter_map(|action| {
+                overrides.get(action)
+                    .and_then(|text| KeyBinding::parse(text))
+                    .or_else(|| KeyBinding::parse(action.default_binding()))
+                    .map(|binding| (binding, *action))
+            })
+            .collect();
+
+        Shortcuts { bindings }
+    }
+
+    pub fn action(&self, key: KeyCode, modifiers: ModifiersState, typing: bool) -> Option<Action> {
+        self.bindings.iter()
+            .find(|(binding, _)| binding.matches(key, modifiers) && !(typing && binding.plain))
+            .map(|(_, action)| *action)
+    }
+
+    pub fn bindings(&self) -> impl Iterator<Item = &(KeyBinding, Action)> {
+        self.bindings.iter()
+    }
+}
+
+
+fn key_code(name: &str) -> Option<(KeyCode, bool)> {
+    let printable = |key| Some((key, true));
+    let special = |key| Some((key, false));
+
+    match name.to_lowercase().as_str() {
+        "a" => printable(KeyCode::A),
+        "b" => printable(KeyCode::B),
+        "c" => printable(KeyCode::C),
+        "d" => printable(KeyCode::D),
+        "e" => printable(KeyCode::E),
+        "f" => printable(KeyCode::F),
+        "g" => printable(KeyCode::G),
+        "h" => printable(KeyCode::H),
+        "i" => printable(KeyCode::I),
+        "j" => printable(KeyCode::J),
+        "k" => printable(KeyCode::K),
+        "l" => printable(KeyCode::L),
+        "m" => printable(KeyCode::M),
+        "n" => printable(KeyCode::N),
+        "o" => printable(KeyCode::O),
+        "p" => printable(KeyCode::P),
+        "q" => printable(KeyCode::Q),
+        "r" => printable(KeyCode::R),
+        "s" => printable(KeyCode::S),
+        "t" => printable(KeyCode::T),
+        "u" => printable(KeyCode::U),
+        "v" => printable(KeyCode::V),
+        "w" => printable(KeyCode::W),
+        "x" => printable(KeyCode::X),
+        "y" => printable(KeyCode::Y),
+        "z" => printable(KeyCode::Z),
+        "0" => printable(KeyCode::Key0),
+        "1" => printable(KeyCode::Key1),
+        "2" => printable(KeyCode::Key2),
+        "3" => printable(KeyCode::Key3),
+        "4" => printable(KeyCode::Key4),
+        "5" => printable(KeyCode::Key5),
+        "6" => printable(KeyCode::Key6),
+        "7" => printable(KeyCode::Key7),
+        "8" => printable(KeyCode::Key8),
+        "9" => printable(KeyCode::Key9),
+        "space" => printable(KeyCode::Space),
+        "enter" | "return" => special(KeyCode::Enter),
+        "escape" | "esc" => special(KeyCode::Escape),
+        "tab" => special(KeyCode::Tab),
+        "backspace" => special(KeyCode::Backspace),
+        "delete" | "del" => special(KeyCode::Delete),
+        "f1" => special(KeyCode::F1),
+        "f2" => special(KeyCode::F2),
+        "f3" => special(KeyCode::F3),
+        "f4" => special(KeyCode::F4),
+        "f5" => special(KeyCode::F5),
+        "f6" => special(KeyCode::F6),
+        "f7" => special(KeyCode::F7),
+        "f8" => special(KeyCode::F8),
+        "f9" => special(KeyCode::F9),
+        "f10" => special(KeyCode::F10),
+        "f11" => special(KeyCode::F11),
+        "f12" => special(KeyCode::F12),
+        _ => None,
+    }
+}
+
+
+fn capitalized(name: &str) -> String {
+    let mut chars = name.chars();
+
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars.flat_map(|c| c.to_lowercase())).collect(),
+        None => String::new(),
+    }
+}
@@ -1,8 +1,15 @@
-use iced::{button, checkbox, radio, Background, Color, Vector, Font};
+use serde_derive::{Deserialize, Serialize};
+use iced::{button, checkbox, container, radio, scrollable, text_input, Background, Color, Vector, Font};
+
+use super::fonts;
 
 pub const WINDOW_HEIGHT: u16 = 600;
 pub const WINDOW_WIDTH: u16 = 400;
 
+// quick-capture strip, see `Settings::compact`
+pub const COMPACT_HEIGHT: u16 = 64;
+pub const COMPACT_WIDTH: u16 = 640;
+
 pub const SECTION_GAP: u16 = 30;
 pub const ITEM_GAP: u16 = 12;
 pub const BUTTON_GAP: u16 = 8;
@@ -10,44 +17,8 @@ pub const LIST_GAP: u16 = 5;
 
 pub const FORM_LAYOUT_LEFT_WIDTH: u16 = 120;
 
-
-#[cfg(not(target_os="windows"))]
-macro_rules! font {
-    ($modifier:expr) => {
-        Font::External {
-            name: concat!("LiberationSans", $modifier),
-            bytes: include_bytes!(concat!("/usr/share/fonts/truetype/liberation/LiberationSans-", $modifier, ".ttf"))
-        }
-    }
-}
-
-#[cfg(target_os="windows")]
-macro_rules! font {
-    ($title:expr, $modifier:expr) => {
-        Font::External {
-            name: concat!("Arial", $title),
-            bytes: include_bytes!(concat!("C:\\Windows\\Fonts\\Arial", $modifier, ".ttf"))
-        }
-    }
-}
-
-
-#[cfg(not(target_os="windows"))]
-const FONTS: [Font; 4] = [
-    font!("Regular"),
-    font!("Italic"),
-    font!("Bold"),
-    font!("BoldItalic"),
-];
-
-
-#[cfg(target_os="windows")]
-const FONTS: [Font; 4] = [
-    font!("Regular", ""),
-    font!("Italic", "i"),
-    font!("Bold", "bd"),
-    font!("BoldItalic", "bi"),
-];
+// `UiBuilder` text sizes are given for this size, and scaled to the chosen one
+pub const BASE_FONT_SIZE: u16 = 18;
 
 
 #[allow(unused)]
@@ -62,79 +33,298 @@ pub enum FontStyle {
 impl Into<Font> for FontStyle {
     fn into(self) -> Font {
         match self {
-            FontStyle::Regular => FONTS[0],
-            FontStyle::Italic => FONTS[1],
-            FontStyle::Bold => FONTS[2],
-            FontStyle::BoldItalic => FONTS[3],
+            FontStyle::Regular => fonts::get(0),
+            FontStyle::Italic => fonts::get(1),
+            FontStyle::Bold => fonts::get(2),
+            FontStyle::BoldItalic => fonts::get(3),
         }
     }
 }
 
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Theme {
+    Light,
+    Dark,
+    HighContrast,
+}
+
+
+impl Theme {
+    pub fn label(self) -> &'static str {
+        match self {
+            Theme::Light => tr!("theme.light"),
+            Theme::Dark => tr!("theme.dark"),
+            Theme::HighContrast => tr!("theme.high-contrast"),
+        }
+    }
+
+    pub fn palette(self) -> Palette {
+        match self {
+            Theme::Light => Palette {
+                background: Color::WHITE,
+                surface: Color::from_rgb8(0xEE, 0xEE, 0xEE),
+                text: Color::from_rgb(0.2, 0.2, 0.2),
+                title: Color::from_rgb(0.1, 0.1, 0.1),
+                placeholder: Color::from_rgb(0.1, 0.1, 0.3),
+                primary: Color::from_rgb(0.11, 0.42, 0.87),
+                secondary: Color::from_rgb(0.5, 0.5, 0.5),
+                danger: Color::from_rgb8(157, 12, 12),
+                button_text: Color::from_rgb8(0xEE, 0xEE, 0xEE),
+                border: Color::from_rgb(0.7, 0.7, 0.7),
+                border_width: 1,
+            },
+            Theme::Dark => Palette {
+                background: Color::from_rgb8(0x1E, 0x1F, 0x22),
+                surface: Color::from_rgb8(0x2B, 0x2D, 0x31),
+                text: Color::from_rgb8(0xD4, 0xD4, 0xD4),
+                title: Color::from_rgb8(0xF0, 0xF0, 0xF0),
+                placeholder: Color::from_rgb8(0x9A, 0xA0, 0xC0),
+                primary: Color::from_rgb8(0x3D, 0x8B, 0xFD),
+                secondary: Color::from_rgb8(0x5A, 0x5D, 0x63),
+                danger: Color::from_rgb8(0xC0, 0x39, 0x2B),
+                button_text: Color::from_rgb8(0xEE, 0xEE, 0xEE),
+                border: Color::from_rgb8(0x4A, 0x4D, 0x52),
+                border_width: 1,
+            },
+            // black and white, with yellow for what matters; borders everywhere
+            Theme::HighContrast => Palette {
+                background: Color::BLACK,
+                surface: Color::from_rgb8(0x33, 0x33, 0x33),
+                text: Color::WHITE,
+                title: Color::WHITE,
+                placeholder: Color::from_rgb8(0xFF, 0xD4, 0x00),
+                primary: Color::from_rgb8(0xFF, 0xD4, 0x00),
+                secondary: Color::WHITE,
+                danger: Color::from_rgb8(0xFF, 0x6B, 0x6B),
+                button_text: Color::BLACK,
+                border: Color::WHITE,
+                border_width: 2,
+            },
+        }
+    }
+}
+
+
+#[derive(Debug, Clone, Copy)]
+pub struct Palette {
+    pub background: Color,
+    // inputs, hovered list entries
+    pub surface: Color,
+    pub text: Color,
+    pub title: Color,
+    pub placeholder: Color,
+    pub primary: Color,
+    pub secondary: Color,
+    pub danger: Color,
+    pub button_text: Color,
+    pub border: Color,
+    pub border_width: u16,
+}
+
+
 pub enum ButtonStyle {
     Primary,
     Secondary,
     Danger,
+    Flat,       // clickable content, e.g. list entries
 }
 
 
 impl ButtonStyle {
-    fn background(&self) -> Background {
-        Background::Color(match self {
-            Self::Primary => Color::from_rgb(0.11, 0.42, 0.87),
-            Self::Secondary => Color::from_rgb(0.5, 0.5, 0.5),
-            Self::Danger => Color::from_rgb8(157, 12, 12),
-        })
+    fn background(&self, palette: &Palette) -> Option<Background> {
+        match self {
+            Self::Primary => Some(palette.primary),
+            Self::Secondary => Some(palette.secondary),
+            Self::Danger => Some(palette.danger),
+            Self::Flat => None,
+        }.map(Background::Color)
     }
+
+    fn accent(&self, palette: &Palette) -> Color {
+        match self {
+            Self::Danger => palette.danger,
+            Self::Primary | Self::Secondary | Self::Flat => palette.primary,
+        }
+    }
+}
+
+
+// a `ButtonStyle` painted with the palette of the current theme
+pub struct Themed {
+    pub style: ButtonStyle,
+    pub palette: Palette,
 }
 
 
-impl button::StyleSheet for ButtonStyle {
+impl button::StyleSheet for Themed {
     fn active(&self) -> button::Style {
-        button::Style {
-            background: Some(self.background()),
-            border_radius: 4,
-            shadow_offset: Vector::new(1.0, 1.0),
-            text_color: Color::from_rgb8(0xEE, 0xEE, 0xEE),
-            ..button::Style::default()
+        match self.style {
+            ButtonStyle::Flat => button::Style {
+                text_color: self.palette.text,
+                ..button::Style::default()
+            },
+            _ => button::Style {
+                background: self.style.background(&self.palette),
+                border_radius: 4,
+                border_width: if self.palette.border_width > 1 { self.palette.border_width } else { 0 },
+                border_color: self.palette.border,
+                shadow_offset: Vector::new(1.0, 1.0),
+                text_color: self.palette.button_text,
+            },
         }
     }
 
     fn hovered(&self) -> button::Style {
-        button::Style {
-            text_color: Color::WHITE,
-            shadow_offset: Vector::new(1.0, 2.0),
-            ..self.active()
+        match self.style {
+            ButtonStyle::Flat => button::Style {
+                background: Some(Background::Color(self.palette.surface)),
+                border_radius: 4,
+                ..self.active()
+            },
+            _ => button::Style {
+                shadow_offset: Vector::new(1.0, 2.0),
+                ..self.active()
+            },
         }
     }
 }
 
 
-impl radio::StyleSheet for ButtonStyle {
+impl radio::StyleSheet for Themed {
     fn active(&self) -> radio::Style {
         radio::Style {
-            ..Box::<dyn iced_style::radio::StyleSheet>::default().active()
+            background: Background::Color(self.palette.surface),
+            dot_color: self.style.accent(&self.palette),
+            border_width: self.palette.border_width,
+            border_color: self.palette.border,
         }
     }
 
     fn hovered(&self) -> radio::Style {
         radio::Style {
-            ..Box::<dyn iced_style::radio::StyleSheet>::default().hovered()
+            border_color: self.style.accent(&self.palette),
+            ..self.active()
         }
     }
 }
 
 
-impl checkbox::StyleSheet for ButtonStyle {
-    fn active(&self, is_checked: bool) -> checkbox::Style {
+impl checkbox::StyleSheet for Themed {
+    fn active(&self, _is_checked: bool) -> checkbox::Style {
         checkbox::Style {
-            ..Box::<dyn iced_style::checkbox::StyleSheet>::default().active(is_checked)
+            background: Background::Color(self.palette.surface),
+            checkmark_color: self.style.accent(&self.palette),
+            border_radius: 4,
+            border_width: self.palette.border_width,
+            border_color: self.palette.border,
         }
     }
 
     fn hovered(&self, is_checked: bool) -> checkbox::Style {
         checkbox::Style {
-            ..Box::<dyn iced_style::checkbox::StyleSheet>::default().hovered(is_checked)
+            border_color: self.style.accent(&self.palette),
+            ..self.active(is_checked)
+        }
+    }
+}
+
+
+pub struct Window(pub Palette);
+
+
+impl container::StyleSheet for Window {
+    fn style(&self) -> container::Style {
+        container::Style {
+            text_color: Some(self.0.text),
+            background: Some(Background::Color(self.0.background)),
+            ..container::Style::default()
+        }
+    }
+}
+
+
+pub struct Input(pub Palette);
+
+
+impl text_input::StyleSheet for Input {
+    fn active(&self) -> text_input::Style {
+        text_input::Style {
+            background: Background::Color(self.0.surface),
+            border_radius: 4,
+            border_width: self.0.border_width,
+            border_color: self.0.border,
+        }
+    }
+
+    fn focused(&self) -> text_input::Style {
+        text_input::Style {
+            border_color: self.0.primary,
+            ..self.active()
+        }
+    }
+
+    fn placeholder_color(&self) -> Color {
+        Color { a: 0.5, ..self.0.text }
+    }
+
+    fn value_color(&self) -> Color {
+        self.0.text
+    }
+
+    fn selection_color(&self) -> Color {
+        Color { a: 0.4, ..self.0.primary }
+    }
+}
+
+
+// a read-only tag
+pub struct Chip(pub Palette);
+
+
+impl container::StyleSheet for Chip {
+    fn style(&self) -> container::Style {
+        container::Style {
+            text_color: Some(self.0.text),
+            background: Some(Background::Color(self.0.surface)),
+            border_radius: 8,
+            border_width: 1,
+            border_color: self.0.border,
+        }
+    }
+}
+
+
+pub struct List(pub Palette);
+
+
+impl scrollable::StyleSheet for List {
+    fn active(&self) -> scrollable::Scrollbar {
+        scrollable::Scrollbar {
+            background: None,
+            border_radius: 2,
+            border_width: 0,
+            border_color: Color::TRANSPARENT,
+            scroller: scrollable::Scroller {
+                color: self.0.secondary,
+                border_radius: 2,
+                border_width: 0,
+                border_color: Color::TRANSPARENT,
+            },
+        }
+    }
+
+    fn hovered(&self) -> scrollable::Scrollbar {
+        let active = self.active();
+
+        scrollable::Scrollbar {
+            background: Some(Background::Color(self.0.surface)),
+            scroller: scrollable::Scroller {
+                color: self.0.primary,
+                ..active.scroller
+            },
+            ..active
         }
     }
 }
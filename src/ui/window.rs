@@ -1,26 +1,369 @@
-use chrono::{DateTime, Utc, Duration};
+use std::collections::BTreeSet;
+use std::path::PathBuf;
+use chrono::{DateTime, Local, Utc, Duration};
 use iced::{
     Application, Command, Subscription,
     executor, text_input, button, scrollable,
 };
 use iced_native::{Event, input::{self, keyboard}};
 
-use crate::record::{Record, HowItWasStopped, WhatWasHappening};
-use crate::app_data::{AppData, LoadError, SaveError};
+use crate::record::{Record, HowItWasStopped, WhatWasHappening, normalize_name};
+use crate::app_data::{self, AppData, LoadError, OnDisk, SaveError};
+use crate::draft;
+use crate::sessions::{SessionSource, WatchedApplication};
+use crate::watcher;
+use crate::spool::{self, SpoolEntry, SpoolFile, SpoolOutcome};
+use crate::import::{self, CsvField, ImportKind, ImportPreview, ImportError};
+use crate::settings::{Activity, Settings, TimeDisplay, Unreadable, built_in_label};
+use crate::i18n::{self, Locale};
+use crate::attachments::{self, Attachment, AttachError};
+use crate::issues::{IssueRef, IssueStatus, KnownIssue, Tracker};
+use crate::clusters::{self, Cluster, Trend};
+use crate::stack_trace::{self, StackTrace, TraceError, TraceFormat};
+use crate::export::{self, ExportError, ExportReport};
+use crate::snapshot::{self, SystemSnapshot};
+use crate::ipc::{self, IpcCommand, IpcRequest};
+use crate::stats::Stats;
+use crate::http_api::{self, HttpRequest, HttpResponse};
+use crate::hooks::{self, HookOutcome, HookStatus};
+use crate::webhooks::{self, Outbox, Delivery};
 
 use super::utils::time_utils;
-use super::style::ButtonStyle;
+use super::style::{self, ButtonStyle, Theme};
 use super::builder::{UiBuilder, ColumnAlignment};
+use super::shortcuts::{Action, Shortcuts};
+use super::screen::Screen;
+use super::on_top;
+use super::fonts;
+
+
+// outcomes of the latest hooks shown in the window, all of them are in hooks.log
+const HOOK_LOG_LEN: usize = 10;
+
+// files taken from the spool directory, the latest first
+const SPOOL_LOG_LEN: usize = 10;
+
+// known tags offered while typing one, and as history filters (most used first)
+const TAG_SUGGESTIONS: usize = 4;
+const TAG_FILTERS: usize = 6;
+// known issues offered as history filters and for linking, most recent first
+const ISSUE_CHIPS: usize = 4;
+
+// frames listed in the detail view, innermost first
+const SHOWN_FRAMES: usize = 20;
 
 
 #[derive(Default, Clone)]
 struct WindowUiEditState {
     record: Record,
+    tag: String,
     // widgets
     description_state: text_input::State,
+    tag_state: text_input::State,
+    add_tag_state: button::State,
+    tag_states: Vec<button::State>,
+    suggestion_states: Vec<button::State>,
     crash_state: button::State,
     killed_state: button::State,
+    compact_state: button::State,
+    application_state: button::State,
+}
+
+
+// a draft found on startup, waiting for the user to finish or discard it
+struct WindowUiDraftState {
+    record: Record,
+    // widgets
+    finish_state: button::State,
+    discard_state: button::State,
+}
+
+
+// records of one known issue, or those never reported
+#[derive(Debug, Clone)]
+pub enum IssueFilter {
+    Unreported,
+    Known(IssueRef),
+}
+
+
+impl IssueFilter {
+    // the issue's status and url may have changed since it was picked
+    fn is(&self, other: &IssueFilter) -> bool {
+        match (self, other) {
+            (IssueFilter::Unreported, IssueFilter::Unreported) => true,
+            (IssueFilter::Known(issue), IssueFilter::Known(other)) => issue.same_issue(other),
+            _ => false,
+        }
+    }
+}
+
+
+#[derive(Default)]
+struct WindowUiHistoryState {
+    search: String,
+    // records must carry all of them
+    tag_filter: BTreeSet<String>,
+    issue_filter: Option<IssueFilter>,
+    // id of a cluster picked on the signatures screen
+    cluster_filter: Option<String>,
+    import: WindowUiImportState,
+    export: WindowUiExportState,
+    detail: Option<WindowUiDetailState>,
+    // widgets
+    search_state: text_input::State,
+    tag_filter_states: Vec<button::State>,
+    issue_filter_states: Vec<button::State>,
+    cluster_filter_state: button::State,
+    save_state: button::State,
     clear_state: button::State,
+    // layout
+    records_scroll_state: scrollable::State,
+    entry_states: Vec<button::State>,
+}
+
+
+impl WindowUiHistoryState {
+    fn text_inputs(&self) -> Vec<&text_input::State> {
+        let mut inputs = vec![&self.search_state, &self.import.path_state, &self.export.path_state];
+        if let Some(detail) = &self.detail {
+            inputs.extend(detail.text_inputs());
+        }
+
+        inputs
+    }
+}
+
+
+#[derive(Default)]
+struct WindowUiSignaturesState {
+    // layout
+    scroll_state: scrollable::State,
+    cluster_states: Vec<button::State>,
+}
+
+
+#[derive(Default)]
+struct WindowUiStatsState {
+    // layout
+    scroll_state: scrollable::State,
+}
+
+
+#[derive(Default)]
+struct WindowUiApplicationsState {
+    name: String,
+    // widgets
+    name_state: text_input::State,
+    start_state: button::State,
+    // layout
+    scroll_state: scrollable::State,
+    session_states: Vec<button::State>,
+}
+
+
+#[derive(Default)]
+struct WindowUiImportState {
+    path: String,
+    preview: Option<ImportPreview>,
+    report: Option<String>,
+    // widgets
+    path_state: text_input::State,
+    open_state: button::State,
+    confirm_state: button::State,
+    cancel_state: button::State,
+    mapping_states: [button::State; 6],
+}
+
+
+#[derive(Default)]
+struct WindowUiExportState {
+    path: String,
+    report: Option<String>,
+    // widgets
+    path_state: text_input::State,
+    export_state: button::State,
+}
+
+
+#[derive(Default)]
+struct WindowUiDetailState {
+    record_id: String,
+    attachment_path: String,
+    trace_path: String,
+    trace_text: String,
+    issue_tracker: Tracker,
+    issue_id: String,
+    issue_url: String,
+    error: Option<String>,
+    // widgets
+    attachment_path_state: text_input::State,
+    attach_state: button::State,
+    remove_states: Vec<button::State>,
+    trace_path_state: text_input::State,
+    load_trace_state: button::State,
+    trace_text_state: text_input::State,
+    parse_trace_state: button::State,
+    remove_trace_state: button::State,
+    tracker_state: button::State,
+    issue_id_state: text_input::State,
+    issue_url_state: text_input::State,
+    link_state: button::State,
+    // status and remove buttons of each linked issue
+    issue_states: Vec<(button::State, button::State)>,
+    known_issue_states: Vec<button::State>,
+    back_state: button::State,
+    delete_state: button::State,
+}
+
+
+impl WindowUiDetailState {
+    // all of them, a shortcut must not fire while typing in any (see `is_typing`)
+    fn text_inputs(&self) -> Vec<&text_input::State> {
+        vec![
+            &self.attachment_path_state,
+            &self.trace_path_state,
+            &self.trace_text_state,
+            &self.issue_id_state,
+            &self.issue_url_state,
+        ]
+    }
+}
+
+
+// the settings being edited, text fields are only parsed on "Apply"
+#[derive(Default)]
+struct WindowUiSettingsState {
+    data_dir: String,
+    sync_dir: String,
+    tick_interval: String,
+    autosave_delay: String,
+    http_port: String,
+    font_family: String,
+    font_size: String,
+    time_display: Option<TimeDisplay>,
+    // shown at once as a preview, kept on "Apply"
+    theme: Option<Theme>,
+    locale: Option<Locale>,
+    activities: Vec<Activity>,
+    activity_label: String,
+    default_what: Option<WhatWasHappening>,
+    autosave: bool,
+    always_on_top: bool,
+    errors: Vec<String>,
+    // applied settings with another data folder, until the user says whether
+    // the records go along
+    pending_move: Option<Settings>,
+    moving: bool,
+    // widgets
+    data_dir_state: text_input::State,
+    sync_dir_state: text_input::State,
+    tick_interval_state: text_input::State,
+    autosave_delay_state: text_input::State,
+    http_port_state: text_input::State,
+    font_family_state: text_input::State,
+    font_size_state: text_input::State,
+    activity_label_state: text_input::State,
+    add_activity_state: button::State,
+    activity_states: Vec<button::State>,
+    default_what_states: Vec<button::State>,
+    cancel_state: button::State,
+    apply_state: button::State,
+    move_state: button::State,
+    switch_state: button::State,
+}
+
+
+impl WindowUiSettingsState {
+    fn text_inputs(&self) -> Vec<&text_input::State> {
+        vec![
+            &self.data_dir_state,
+            &self.sync_dir_state,
+            &self.tick_interval_state,
+            &self.autosave_delay_state,
+            &self.http_port_state,
+            &self.font_family_state,
+            &self.font_size_state,
+            &self.activity_label_state,
+        ]
+    }
+
+    fn new(settings: &Settings) -> Self {
+        let dir_text = |dir: &Option<PathBuf>| dir.as_ref()
+            .map(|dir| dir.display().to_string())
+            .unwrap_or_default();
+
+        Self {
+            data_dir: dir_text(&settings.data_dir),
+            sync_dir: dir_text(&settings.sync_dir),
+            tick_interval: settings.tick_interval_ms.to_string(),
+            autosave_delay: settings.autosave_delay_secs.to_string(),
+            http_port: settings.http_port.map(|port| port.to_string()).unwrap_or_default(),
+            font_family: settings.font_family.clone().unwrap_or_default(),
+            font_size: settings.font_size.to_string(),
+            time_display: Some(settings.time_display),
+            theme: Some(settings.theme),
+            locale: settings.locale,
+            activities: settings.activities.clone(),
+            default_what: settings.default_what.clone(),
+            autosave: settings.autosave,
+            always_on_top: settings.always_on_top,
+            ..Self::default()
+        }
+    }
+
+    // the edited copy of `current`, or what is wrong with it
+    fn parse(&self, current: &Settings) -> Result<Settings, Vec<String>> {
+        let mut errors = vec![];
+        let dir = |text: &str| {
+            let text = text.trim();
+            if text.is_empty() { None } else { Some(PathBuf::from(text)) }
+        };
+
+        let mut settings = current.clone();
+        settings.data_dir = dir(&self.data_dir);
+        settings.sync_dir = dir(&self.sync_dir);
+        settings.time_display = self.time_display.unwrap_or(current.time_display);
+        settings.theme = self.theme.unwrap_or(current.theme);
+        settings.activities = self.activities.clone();
+        settings.default_what = self.default_what.clone();
+        settings.locale = self.locale;
+        settings.font_family = Some(self.font_family.trim())
+            .filter(|family| !family.is_empty())
+            .map(String::from);
+        settings.autosave = self.autosave;
+        settings.always_on_top = self.always_on_top;
+
+        match self.tick_interval.trim().parse() {
+            Ok(value) => settings.tick_interval_ms = value,
+            Err(_) => errors.push(tr!("settings.error.tick-number").into()),
+        }
+
+        match self.font_size.trim().parse() {
+            Ok(value) => settings.font_size = value,
+            Err(_) => errors.push(tr!("settings.error.font-size-number").into()),
+        }
+
+        match self.autosave_delay.trim().parse() {
+            Ok(value) => settings.autosave_delay_secs = value,
+            Err(_) => errors.push(tr!("settings.error.delay-number").into()),
+        }
+
+        settings.http_port = match self.http_port.trim() {
+            "" => None,
+            port => match port.parse() {
+                Ok(port) => Some(port),
+                Err(_) => {
+                    errors.push(tr!("settings.error.port-number").into());
+                    None
+                },
+            },
+        };
+
+        errors.extend(settings.validate());
+
+        if errors.is_empty() { Ok(settings) } else { Err(errors) }
+    }
 }
 
 
@@ -29,98 +372,369 @@ struct WindowUiState {
     layout_debug: bool,
     last_tick: Option<DateTime<Utc>>,
     edit: WindowUiEditState,
-    // layout
-    records_scroll_state: scrollable::State,
+    draft: Option<WindowUiDraftState>,
+    history: WindowUiHistoryState,
+    signatures: WindowUiSignaturesState,
+    stats: WindowUiStatsState,
+    applications: WindowUiApplicationsState,
+    // only while on the settings screen
+    settings: Option<WindowUiSettingsState>,
+    local_origin: String,
+    shortcuts: Shortcuts,
+    hook_log: Vec<HookOutcome>,
+    spool_log: Vec<SpoolOutcome>,
+    // settings.json couldn't be read, until dismissed
+    settings_warning: Option<String>,
+    cheat_sheet: bool,
+    // what the window manager was last asked, None until the first tick
+    on_top: Option<bool>,
+    // widgets
+    screen_states: [button::State; 6],
+    shortcuts_state: button::State,
+    spool_clear_state: button::State,
+    settings_warning_state: button::State,
+    cheat_sheet_close_state: button::State,
 }
 
 
 #[derive(Default)]
 pub struct MainWindow {
+    settings: Settings,
     data: Option<AppData>,
+    // records.json as last read or written, and the last look for changes to it
+    disk: Option<OnDisk>,
+    last_disk_check: Option<DateTime<Utc>>,
+    checking_disk: bool,
+    // counts the changes to the data, to tell whether it moved while it was
+    // being saved or merged with the file
+    changes: u64,
+    outbox: Outbox,
+    delivering: bool,
+    // changes not written yet, see `Settings::autosave`
+    unsaved_since: Option<DateTime<Utc>>,
+    // the description was typed in since the draft was last written
+    draft_dirty: bool,
+    // last look for the watched processes, see `watcher`
+    last_scan: Option<DateTime<Utc>>,
+    // last look into the spool directory, and files of it being handled
+    last_spool: Option<DateTime<Utc>>,
+    spooling: bool,
     ui: WindowUiState,
 }
 
 
 #[derive(Debug, Clone)]
 pub enum Message {
-    DataLoaded(Result<AppData, LoadError>),
-    Saved(Result<(), SaveError>),
+    DataLoaded(Result<(AppData, OnDisk), LoadError>),
+    // what was sent to be written, and the file as it was written
+    Saved(AppData, u64, Result<OnDisk, SaveError>),
+    DiskChanged(u64, Option<(OnDisk, AppData)>),
+    SettingsSaved(Result<(), SaveError>),
     Tick(DateTime<Utc>),
     EventOccurred(Event),
     WhatChanged(WhatWasHappening),
+    ActivitySelected(usize),
     DescriptionEdited(String),
+    TagEdited(String),
+    TagAdded(String),
+    TagRemoved(String),
+    TagFilterToggled(String),
+    IssueFilterToggled(IssueFilter),
+    ClusterSelected(String),
+    ClusterFilterCleared,
+    IssueTrackerCycled,
+    IssueIdEdited(String),
+    IssueUrlEdited(String),
+    IssueLinked,
+    KnownIssueLinked(IssueRef),
+    IssueStatusCycled(IssueRef),
+    IssueUnlinked(usize),
     FrozenToggled(bool),
     BusyToggled(bool),
+    SnapshotToggled(bool),
     CrashClicked,
     KilledClicked,
     ClearClicked,
+    RecordApplicationCycled,
+    ApplicationNameEdited(String),
+    ApplicationStarted(String),
+    ApplicationStopped(String),
+    WatcherScanned(BTreeSet<String>),
+    SpoolCollected(Vec<SpoolFile>),
+    SpoolFiled(Vec<SpoolOutcome>),
+    SpoolLogCleared,
+    SettingsWarningClosed,
+    ImportPathEdited(String),
+    ImportOpenClicked,
+    ImportPreviewed(Result<ImportPreview, ImportError>),
+    ImportMappingCycled(CsvField),
+    ImportConfirmed,
+    ImportCancelled,
+    ImportRead(Result<Vec<Record>, ImportError>),
+    ScreenSelected(Screen),
+    SettingsReverted,
+    DataDirEdited(String),
+    SyncDirEdited(String),
+    TickIntervalEdited(String),
+    AutosaveDelayEdited(String),
+    HttpPortEdited(String),
+    FontFamilyEdited(String),
+    FontSizeEdited(String),
+    TimeDisplayChanged(TimeDisplay),
+    LocaleChanged(Option<Locale>),
+    DefaultWhatChanged(Option<WhatWasHappening>),
+    ActivityLabelEdited(String),
+    ActivityAdded,
+    ActivityRemoved(usize),
+    ThemeChanged(Theme),
+    AutosaveToggled(bool),
+    AlwaysOnTopToggled(bool),
+    CompactToggled,
+    OnTopApplied(()),
+    WindowResized(()),
+    SettingsApplied,
+    // true to take the records along to the new data folder
+    DataDirMoveChosen(bool),
+    DataMoved(Settings, Result<(), SaveError>),
+    SaveClicked,
+    EntrySelected(String),
+    DetailClosed,
+    AttachmentPathEdited(String),
+    AttachClicked,
+    Attached(String, Result<Attachment, AttachError>),
+    AttachmentRemoved(usize),
+    TracePathEdited(String),
+    TraceLoadClicked,
+    TraceTextEdited(String),
+    TraceParseClicked,
+    TraceLoaded(String, Result<StackTrace, TraceError>),
+    TraceRemoved,
+    RecordDeleted,
+    AttachmentsCleaned(()),
+    ExportPathEdited(String),
+    ExportClicked,
+    Exported(Result<ExportReport, ExportError>),
+    SearchEdited(String),
+    CheatSheetToggled,
+    IpcReceived(IpcRequest),
+    HttpReceived(HttpRequest),
+    HookFinished(HookOutcome),
+    SnapshotCaptured(String, Option<SystemSnapshot>),
+    OutboxLoaded(Outbox),
+    DraftLoaded(Option<Record>),
+    DraftSaved(Result<(), SaveError>),
+    DraftFinished,
+    DraftDiscarded,
+    OutboxSaved(Result<(), SaveError>),
+    WebhooksDelivered(Vec<(Delivery, Result<(), String>)>),
 }
 
 
 impl Application for MainWindow {
     type Executor = executor::Default;
     type Message = Message;
-    type Flags = ();
+    type Flags = (Settings, Option<Unreadable>);
+
+    fn new((settings, unreadable): Self::Flags) -> (Self, Command<Self::Message>) {
+        let mut window = Self::default();
+        window.ui.settings_warning = unreadable.map(|unreadable| match unreadable.copy {
+            Some(copy) => tr!("settings.unreadable",
+                              path = unreadable.path.display(),
+                              copy = copy.display()),
+            None => tr!("settings.unreadable-lost", path = unreadable.path.display()),
+        });
+        let load = AppData::load(settings.sync_dir.clone());
+
+        window.ui.edit.record.what = settings.default_what.clone();
+        window.ui.local_origin = format!("{}@{}", crate::host::user_name(), crate::host::host_name());
+        window.ui.shortcuts = Shortcuts::new(&settings.shortcuts);
+        if settings.screen == Screen::Settings {
+            window.ui.settings = Some(WindowUiSettingsState::new(&settings));
+        }
+        window.settings = settings;
 
-    fn new(_: Self::Flags) -> (Self, Command<Self::Message>) {
         (
-            Self::default(),
-            Command::perform(AppData::load(), Message::DataLoaded)
+            window,
+            Command::batch(vec![
+                Command::perform(load, Message::DataLoaded),
+                Command::perform(Outbox::load(), Message::OutboxLoaded),
+                Command::perform(draft::load(), Message::DraftLoaded),
+            ])
         )
     }
 
     fn title(&self) -> String {
-        "Crash recorder".into()
+        tr!("app.title").into()
     }
 
     fn subscription(&self) -> Subscription<Self::Message> {
         Subscription::batch(vec![
-            time_utils::every(std::time::Duration::from_millis(1000)).map(Message::Tick),
+            time_utils::every(std::time::Duration::from_millis(self.settings.tick_interval_ms)).map(Message::Tick),
             iced_native::subscription::events().map(Message::EventOccurred),
+            ipc::listen(ipc::socket_path()).map(Message::IpcReceived),
+            http_api::listen(self.settings.http_port).map(Message::HttpReceived),
         ])
     }
 
     fn update(&mut self, msg: Message) -> Command<Self::Message> {
         match msg {
-            Message::DataLoaded(Ok(data)) => {
+            Message::DataLoaded(Ok((data, disk))) => {
                 self.data = Some(data);
+                self.disk = Some(disk);
             },
             Message::DataLoaded(Err(_)) => {
                 self.data = Some(AppData::default());
+                self.disk = None;
+            },
+            Message::Saved(sent, changes, Ok(disk)) => {
+                // written to a folder since left
+                if disk.path != AppData::path() {
+                    return Command::none();
+                }
+
+                if changes == self.changes {
+                    self.data = Some(disk.data.clone());
+                    self.disk = Some(disk);
+                }
+                else {
+                    // changed while writing: the next save merges what was
+                    // written, ours having started from what was sent
+                    self.disk = Some(OnDisk { data: sent, hash: None, ..disk });
+                }
+            },
+            Message::Saved(_, _, Err(_)) => {
+                // kept dirty, the next tick tries again
+                self.unsaved_since.get_or_insert_with(Utc::now);
+            },
+            Message::SettingsSaved(_) => (),
+            Message::DiskChanged(changes, changed) => {
+                self.checking_disk = false;
+
+                // changed meanwhile, the next look merges again
+                match changed {
+                    Some((theirs, merged)) if theirs.path == AppData::path() && changes == self.changes => {
+                        self.data = Some(merged);
+                        self.disk = Some(theirs);
+                    },
+                    _ => (),
+                }
             },
-            Message::Saved(_) => (),
             Message::Tick(when) => {
                 self.ui.last_tick = Some(when);
+                let draft =
+                    if self.draft_dirty { self.save_draft() }
+                    else { Command::none() };
+
+                return Command::batch(vec![
+                    self.autosave(when),
+                    self.check_disk(when),
+                    self.deliver_webhooks(),
+                    self.apply_on_top(),
+                    self.scan_watched(when),
+                    self.collect_spool(when),
+                    draft,
+                ]);
             },
             Message::EventOccurred(event) => {
-                match event {
-                    Event::Keyboard(keyboard::Event::Input{
-                        state: input::ButtonState::Pressed,
-                        key_code: keyboard::KeyCode::F11,
-                        modifiers: keyboard::ModifiersState {
-                            shift: false, control: false, alt: false, logo: false,
-                        }
-                    }) => {
-                        self.ui.layout_debug = !self.ui.layout_debug;
-                    },
-                    _ => ()
+                if let Event::Keyboard(keyboard::Event::Input{
+                    state: input::ButtonState::Pressed,
+                    key_code,
+                    modifiers,
+                }) = event {
+                    if let Some(action) = self.ui.shortcuts.action(key_code, modifiers, self.is_typing()) {
+                        return self.run_action(action);
+                    }
                 }
             },
             Message::WhatChanged(what) => {
                 self.ui.edit.record.what = Some(what);
+                return self.save_draft();
+            },
+            // the radio grid only knows positions in `Settings::activities`
+            Message::ActivitySelected(i) => {
+                let what = self.settings.activities().get(i).map(|(what, _)| what.clone());
+                if let Some(what) = what {
+                    return self.update(Message::WhatChanged(what));
+                }
             },
             Message::DescriptionEdited(value) => {
+                // written on the next tick rather than on each key
                 self.ui.edit.record.description = value;
+                self.draft_dirty = true;
+            },
+            Message::TagEdited(value) => {
+                self.ui.edit.tag = value;
+            },
+            Message::TagAdded(text) => {
+                self.ui.edit.tag.clear();
+                if self.ui.edit.record.add_tag(&text) {
+                    return self.save_draft();
+                }
+            },
+            Message::TagRemoved(tag) => {
+                self.ui.edit.record.tags.retain(|kept| *kept != tag);
+                return self.save_draft();
+            },
+            Message::TagFilterToggled(tag) => {
+                let filter = &mut self.ui.history.tag_filter;
+                if !filter.remove(&tag) {
+                    filter.insert(tag);
+                }
+            },
+            Message::IssueFilterToggled(filter) => {
+                let history = &mut self.ui.history;
+                history.issue_filter =
+                    if history.issue_filter.as_ref().map_or(false, |current| current.is(&filter)) { None }
+                    else { Some(filter) };
+            },
+            Message::ClusterSelected(id) => {
+                self.ui.history.cluster_filter = Some(id);
+                return self.update(Message::ScreenSelected(Screen::History));
+            },
+            Message::ClusterFilterCleared => {
+                self.ui.history.cluster_filter = None;
             },
             Message::FrozenToggled(checked) => {
                 self.ui.edit.record.frozen =
                     if checked { Some(Utc::now()) }
                     else { None };
+                return self.save_draft();
             },
             Message::BusyToggled(checked) => {
                 self.ui.edit.record.busy =
                     if checked { Some(Utc::now()) }
                     else { None };
+                return self.save_draft();
+            },
+            Message::DraftLoaded(Some(record)) => {
+                self.ui.draft = Some(WindowUiDraftState {
+                    record,
+                    finish_state: button::State::new(),
+                    discard_state: button::State::new(),
+                });
+            },
+            Message::DraftLoaded(None) => (),
+            Message::DraftSaved(_) => (),
+            Message::DraftFinished => {
+                if let Some(draft) = self.ui.draft.take() {
+                    self.ui.edit.record = draft.record;
+                    if self.settings.screen != Screen::Record && !self.settings.compact {
+                        return Command::batch(vec![
+                            self.update(Message::ScreenSelected(Screen::Record)),
+                            self.save_draft(),
+                        ]);
+                    }
+                }
+                return self.save_draft();
+            },
+            Message::DraftDiscarded => {
+                // the file now follows the current form, blank most of the time
+                self.ui.draft = None;
+                return self.save_draft();
+            },
+            Message::SnapshotToggled(checked) => {
+                self.settings.capture_snapshot = checked;
+                return Command::perform(self.settings.clone().save(), Message::SettingsSaved);
             },
             Message::CrashClicked => {
                 self.ui.edit.record.how = HowItWasStopped::SelfCrashed;
@@ -133,198 +747,2483 @@ impl Application for MainWindow {
                 return self.register_entry();
             },
             Message::ClearClicked => {
+                let mut record_ids = vec![];
                 if let Some(ref mut data) = &mut self.data {
-                    *data = AppData::default();
+                    record_ids = data.records.iter().map(|record| record.id.clone()).collect();
+                    // sessions stay, they are not part of the history
+                    data.records.clear();
                 };
-                return self.save_command();
+                return Command::batch(vec![
+                    Command::perform(attachments::remove_all(record_ids), Message::AttachmentsCleaned),
+                    self.save_command(),
+                ]);
             },
-        }
+            Message::RecordApplicationCycled => {
+                let applications = self.data.as_ref().map(AppData::applications).unwrap_or_default();
+                let current = self.ui.edit.record.application.as_ref()
+                    .and_then(|application| applications.iter().position(|known| known == application));
 
-        Command::none()
-    }
+                // none, then each known application in turn
+                self.ui.edit.record.application = match current {
+                    None => applications.first().cloned(),
+                    Some(i) => applications.get(i + 1).cloned(),
+                };
+                return self.save_draft();
+            },
+            Message::ApplicationNameEdited(value) => {
+                self.ui.applications.name = value;
+            },
+            Message::ApplicationStarted(application) => {
+                if self.start_session(&application, SessionSource::Manual) {
+                    self.ui.applications.name.clear();
+                    return self.save_command();
+                }
+            },
+            Message::ApplicationStopped(application) => {
+                if self.data.as_mut().and_then(|data| data.end_session(&application, Utc::now())).is_some() {
+                    return self.save_command();
+                }
+            },
+            Message::SpoolCollected(files) => {
+                let now = Utc::now();
+                let mut commands = vec![];
+                let mut outcomes = vec![];
+                let mut merged = false;
 
-    fn view(&mut self) -> UiElement!() {
-        let builder = UiBuilder::new();
-        let now = self.ui.last_tick.unwrap_or_else(Utc::now);
-        let duration_format = |d: Duration| format!(
-            "{:02}:{:02}:{:02}",
-            d.num_hours(),
-            d.num_minutes(),
-            d.num_seconds());
+                for file in files {
+                    let name = file.name();
+                    let result = match file.entry {
+                        // another recorder's: kept as it is, without the hooks
+                        Ok(SpoolEntry::Record(mut record)) => {
+                            record.stamp_imported();
+                            let id = record.id.clone();
+                            match &mut self.data {
+                                Some(data) if data.merge(vec![record]).added == 1 => {
+                                    merged = true;
+                                    Ok(id)
+                                },
+                                _ => Err(tr!("spool.exists").into()),
+                            }
+                        },
+                        Ok(SpoolEntry::Input(input)) => match spool::new_record(input, now) {
+                            Ok(record) if self.data.as_ref().map_or(false, |data| data.record(&record.id).is_some()) =>
+                                Err(tr!("spool.exists").into()),
+                            Ok(record) => {
+                                let id = record.id.clone();
+                                commands.push(self.commit_record(record));
+                                Ok(id)
+                            },
+                            Err(error) => Err(error),
+                        },
+                        Err(error) => Err(error),
+                    };
 
-        let records_len = self.data.as_ref().map_or(0, |data| data.records.len());
+                    outcomes.push((file.path, SpoolOutcome { file: name, when: now, result, filed: false }));
+                }
 
-        let frozen_spent = match self.ui.edit.record.frozen {
-            Some(when) => format!(" {} ago", duration_format(now - when)),
-            None => String::new()
-        };
+                if merged {
+                    commands.push(self.save_command());
+                }
 
-        let busy_spent = match self.ui.edit.record.busy {
-            Some(when) => format!(" {} ago", duration_format(now - when)),
-            None => String::new()
-        };
+                commands.push(Command::perform(spool::file_away(outcomes), Message::SpoolFiled));
 
-        //
-        let crash_button = builder.button(
-            &mut self.ui.edit.crash_state,
-            "Crashed",
-            ButtonStyle::Secondary,
-            Message::CrashClicked);
+                return Command::batch(commands);
+            },
+            Message::SpoolFiled(outcomes) => {
+                self.spooling = false;
+                for outcome in outcomes {
+                    self.ui.spool_log.insert(0, outcome);
+                }
+                self.ui.spool_log.truncate(SPOOL_LOG_LEN);
+            },
+            Message::SpoolLogCleared => {
+                self.ui.spool_log.clear();
+            },
+            Message::SettingsWarningClosed => {
+                self.ui.settings_warning = None;
+            },
+            Message::WatcherScanned(running) => {
+                let data = match &mut self.data {
+                    Some(data) => data,
+                    None => return Command::none(),
+                };
+                let now = Utc::now();
+                let mut changed = false;
 
-        let killed_button = builder.button(
-            &mut self.ui.edit.killed_state,
-            "Killed",
-            ButtonStyle::Secondary,
-            Message::KilledClicked);
+                for watched in self.settings.watched.iter() {
+                    let source = data.open_session(&watched.application).map(|session| session.source);
 
-        let history_right_part =
-            if records_len > 0 {
-               vec![builder.button(
-                   &mut self.ui.edit.clear_state,
-                   "Clear",
-                   ButtonStyle::Danger,
-                   Message::ClearClicked)]
-            }
-            else { vec![] };
+                    match (running.contains(&watched.application), source) {
+                        (true, None) => {
+                            changed |= data.start_session(&watched.application, SessionSource::Watcher, now);
+                        },
+                        // sessions started by hand or from the CLI are theirs to close
+                        (false, Some(SessionSource::Watcher)) => {
+                            changed |= data.end_session(&watched.application, now).is_some();
+                        },
+                        _ => (),
+                    }
+                }
 
-        let rows = vec![
-            builder.title("Record"),
-            builder.item_vspacer(),
-            builder.two_col_row(
-                vec![builder.radio(WhatWasHappening::Typing,
-                          "Typing",
-                          self.ui.edit.record.what,
-                          ButtonStyle::Secondary,
-                          Message::WhatChanged)],
-                vec![builder.radio(WhatWasHappening::Running,
-                          "Running",
-                          self.ui.edit.record.what,
-                          ButtonStyle::Secondary,
-                          Message::WhatChanged)],
-                ColumnAlignment::Left
-            ),
-            builder.item_vspacer(),
-            builder.two_col_row(
-                vec![builder.radio(WhatWasHappening::Testing,
-                          "Testing",
-                          self.ui.edit.record.what,
-                          ButtonStyle::Secondary,
-                          Message::WhatChanged)],
-                vec![builder.radio(WhatWasHappening::Debugging,
-                          "Debugging",
-                          self.ui.edit.record.what,
-                          ButtonStyle::Secondary,
-                          Message::WhatChanged)],
-                ColumnAlignment::Left
-            ),
-            builder.item_vspacer(),
-            builder.input(&mut self.ui.edit.description_state,
-                          "Description...",
-                          &self.ui.edit.record.description,
-                          Message::DescriptionEdited),
-            builder.item_vspacer(),
-            builder.form_row(
-                builder.checkbox(self.ui.edit.record.frozen.is_some(),
-                                 "Frozen",
-                                 ButtonStyle::Secondary,
-                                 Message::FrozenToggled),
-                builder.label(frozen_spent),
-            ),
-            builder.item_vspacer(),
-            builder.form_row(
-                builder.checkbox(self.ui.edit.record.busy.is_some(),
-                                 "Busy",
-                                 ButtonStyle::Secondary,
-                                 Message::BusyToggled),
-                builder.label(busy_spent),
-            ),
-            builder.item_vspacer(),
-            builder.two_col_row(vec![], vec![crash_button, killed_button], ColumnAlignment::Right),
-            builder.section_vspacer(),
-            builder.two_col_row(
-                vec![builder.title(format!("History ({})", records_len))],
-                history_right_part,
-                ColumnAlignment::Outward),
-            builder.item_vspacer(),
-            match &self.data {
-                None => {
-                    builder.placeholder("No records.")
-                },
-                Some(ref data) if data.records.is_empty() => {
-                    builder.placeholder("No records.")
-                },
-                Some(ref data) => {
-                    builder.list(&mut self.ui.records_scroll_state,
-                                 data.records .iter()
-                                     .map(|record| Self::make_entry(&builder, record))
-                                     .collect())
+                if changed {
+                    return self.save_command();
                 }
-            }
-        ];
+            },
+            Message::ImportPathEdited(value) => {
+                self.ui.history.import.path = value;
+            },
+            Message::ImportOpenClicked => {
+                self.ui.history.import.report = None;
+                let path = PathBuf::from(self.ui.history.import.path.trim());
+                return Command::perform(import::preview(path), Message::ImportPreviewed);
+            },
+            Message::ImportPreviewed(Ok(preview)) => {
+                self.ui.history.import.preview = Some(preview);
+            },
+            Message::ImportPreviewed(Err(err)) => {
+                self.ui.history.import.preview = None;
+                self.ui.history.import.report = Some(Self::import_error_text(&err));
+            },
+            Message::ImportMappingCycled(field) => {
+                if let Some(ImportPreview { kind: ImportKind::Csv { headers, mapping, .. }, .. }) =
+                    &mut self.ui.history.import.preview {
+                    mapping.cycle(field, headers.len());
+                }
+            },
+            Message::ImportConfirmed => {
+                if let Some(preview) = self.ui.history.import.preview.take() {
+                    return Command::perform(import::records(preview), Message::ImportRead);
+                }
+            },
+            Message::ImportCancelled => {
+                self.ui.history.import.preview = None;
+            },
+            Message::ImportRead(Ok(records)) => {
+                if let Some(ref mut data) = &mut self.data {
+                    let report = data.merge(records);
+                    self.ui.history.import.path.clear();
+                    self.ui.history.import.report = Some(trn!("import.report",
+                                                      report.added,
+                                                      skipped = report.skipped));
+                    return self.save_command();
+                }
+            },
+            Message::ImportRead(Err(err)) => {
+                self.ui.history.import.report = Some(Self::import_error_text(&err));
+            },
+            Message::ScreenSelected(screen) => {
+                self.ui.cheat_sheet = false;
+                self.ui.history.detail = None;
+                self.ui.settings =
+                    if screen == Screen::Settings { Some(WindowUiSettingsState::new(&self.settings)) }
+                    else { None };
 
-        builder.root(self.ui.layout_debug, rows)
-    }
-}
+                if self.settings.screen != screen {
+                    self.settings.screen = screen;
+                    return Command::perform(self.settings.clone().save(), Message::SettingsSaved);
+                }
+            },
+            Message::SettingsReverted => {
+                self.ui.settings = Some(WindowUiSettingsState::new(&self.settings));
+            },
+            Message::DataDirEdited(value) => {
+                if let Some(form) = &mut self.ui.settings {
+                    form.data_dir = value;
+                }
+            },
+            Message::SyncDirEdited(value) => {
+                if let Some(form) = &mut self.ui.settings {
+                    form.sync_dir = value;
+                }
+            },
+            Message::TickIntervalEdited(value) => {
+                if let Some(form) = &mut self.ui.settings {
+                    form.tick_interval = value;
+                }
+            },
+            Message::AutosaveDelayEdited(value) => {
+                if let Some(form) = &mut self.ui.settings {
+                    form.autosave_delay = value;
+                }
+            },
+            Message::HttpPortEdited(value) => {
+                if let Some(form) = &mut self.ui.settings {
+                    form.http_port = value;
+                }
+            },
+            Message::FontFamilyEdited(value) => {
+                if let Some(form) = &mut self.ui.settings {
+                    form.font_family = value;
+                }
+            },
+            Message::FontSizeEdited(value) => {
+                if let Some(form) = &mut self.ui.settings {
+                    form.font_size = value;
+                }
+            },
+            Message::TimeDisplayChanged(value) => {
+                if let Some(form) = &mut self.ui.settings {
+                    form.time_display = Some(value);
+                }
+            },
+            Message::LocaleChanged(value) => {
+                if let Some(form) = &mut self.ui.settings {
+                    form.locale = value;
+                }
+            },
+            Message::DefaultWhatChanged(value) => {
+                if let Some(form) = &mut self.ui.settings {
+                    form.default_what = value;
+                }
+            },
+            Message::ActivityLabelEdited(value) => {
+                if let Some(form) = &mut self.ui.settings {
+                    form.activity_label = value;
+                }
+            },
+            // checked with the rest on "Apply"
+            Message::ActivityAdded => {
+                if let Some(form) = &mut self.ui.settings {
+                    if let Some(activity) = Activity::new(&form.activity_label) {
+                        form.activities.push(activity);
+                        form.activity_label.clear();
+                    }
+                }
+            },
+            Message::ActivityRemoved(i) => {
+                if let Some(form) = &mut self.ui.settings {
+                    if i < form.activities.len() {
+                        let removed = WhatWasHappening::from(form.activities.remove(i).name);
+                        if form.default_what.as_ref() == Some(&removed) {
+                            form.default_what = None;
+                        }
+                    }
+                }
+            },
+            Message::ThemeChanged(theme) => {
+                if let Some(form) = &mut self.ui.settings {
+                    form.theme = Some(theme);
+                }
+            },
+            Message::AutosaveToggled(checked) => {
+                if let Some(form) = &mut self.ui.settings {
+                    form.autosave = checked;
+                }
+            },
+            Message::AlwaysOnTopToggled(checked) => {
+                if let Some(form) = &mut self.ui.settings {
+                    form.always_on_top = checked;
+                }
+            },
+            Message::CompactToggled => {
+                self.settings.compact = !self.settings.compact;
+                self.ui.cheat_sheet = false;
 
-impl MainWindow {
+                let (width, height) =
+                    if self.settings.compact { (style::COMPACT_WIDTH, style::COMPACT_HEIGHT) }
+                    else { (style::WINDOW_WIDTH, style::WINDOW_HEIGHT) };
 
-    fn register_entry(&mut self) -> Command<UiMessage!()>
-    {
-        if let Some(ref mut data) = &mut self.data.as_mut() {
-            let edit = std::mem::take(&mut self.ui.edit);
-            data.records.push(edit.record);
-        };
+                return Command::batch(vec![
+                    Command::perform(self.settings.clone().save(), Message::SettingsSaved),
+                    Command::perform(on_top::resize(self.title(), width, height), Message::WindowResized),
+                    self.apply_on_top(),
+                ]);
+            },
+            Message::OnTopApplied(()) => (),
+            Message::WindowResized(()) => (),
+            Message::SettingsApplied => {
+                let parsed = match &self.ui.settings {
+                    Some(form) => form.parse(&self.settings),
+                    None => return Command::none(),
+                };
 
-        self.save_command()
+                match parsed {
+                    Ok(settings) if settings.data_dir != self.settings.data_dir => {
+                        if let Some(form) = &mut self.ui.settings {
+                            form.errors.clear();
+                            form.pending_move = Some(settings);
+                        }
+                    },
+                    Ok(settings) => return self.apply_settings(settings),
+                    Err(errors) => {
+                        if let Some(form) = &mut self.ui.settings {
+                            form.errors = errors;
+                        }
+                    },
+                }
+            },
+            Message::DataDirMoveChosen(take_along) => {
+                let settings = match self.ui.settings.as_mut().and_then(|form| form.pending_move.take()) {
+                    Some(settings) => settings,
+                    None => return Command::none(),
+                };
+
+                match &self.data {
+                    Some(data) if take_along => {
+                        if let Some(form) = &mut self.ui.settings {
+                            form.moving = true;
+                        }
+                        // written over there instead
+                        self.unsaved_since = None;
+
+                        let from = AppData::data_dir();
+                        let to = AppData::resolve_data_dir(settings.data_dir.clone());
+                        return Command::perform(data.clone().move_data(from, to),
+                                                move |result| Message::DataMoved(settings.clone(), result));
+                    },
+                    _ => return self.apply_settings(settings),
+                }
+            },
+            Message::DataMoved(settings, Ok(())) => {
+                return self.apply_settings(settings);
+            },
+            Message::DataMoved(_, Err(_)) => {
+                self.unsaved_since.get_or_insert_with(Utc::now);
+                if let Some(form) = &mut self.ui.settings {
+                    form.moving = false;
+                    form.errors = vec![tr!("settings.error.move-failed").into()];
+                }
+            },
+            Message::SaveClicked => {
+                return self.write_data();
+            },
+            Message::EntrySelected(record_id) => {
+                self.ui.history.detail = Some(WindowUiDetailState {
+                    record_id,
+                    ..WindowUiDetailState::default()
+                });
+            },
+            Message::DetailClosed => {
+                self.ui.history.detail = None;
+            },
+            Message::AttachmentPathEdited(value) => {
+                if let Some(detail) = &mut self.ui.history.detail {
+                    detail.attachment_path = value;
+                }
+            },
+            Message::AttachClicked => {
+                if let Some(detail) = &mut self.ui.history.detail {
+                    let record_id = detail.record_id.clone();
+                    let source = PathBuf::from(detail.attachment_path.trim());
+                    let existing = self.data.as_ref()
+                        .and_then(|data| data.record(&record_id))
+                        .map(|record| record.attachments.clone())
+                        .unwrap_or_default();
+
+                    detail.error = None;
+                    return Command::perform(
+                        attachments::attach(record_id.clone(), source, existing),
+                        move |result| Message::Attached(record_id.clone(), result));
+                }
+            },
+            Message::Attached(record_id, Ok(attachment)) => {
+                if let Some(record) = self.data.as_mut().and_then(|data| data.record_mut(&record_id)) {
+                    record.attachments.push(attachment);
+                    if let Some(detail) = &mut self.ui.history.detail {
+                        detail.attachment_path.clear();
+                    }
+                    return self.save_command();
+                }
+            },
+            Message::Attached(_, Err(err)) => {
+                if let Some(detail) = &mut self.ui.history.detail {
+                    detail.error = Some(match err {
+                        AttachError::FileError => tr!("attach.read-error").into(),
+                        AttachError::TooLarge => tr!(
+                            "attach.too-large",
+                            file = attachments::MAX_FILE_SIZE / (1024 * 1024),
+                            record = attachments::MAX_RECORD_SIZE / (1024 * 1024)),
+                        AttachError::CopyError => tr!("attach.copy-error").into(),
+                    });
+                }
+            },
+            Message::IssueTrackerCycled => {
+                if let Some(detail) = &mut self.ui.history.detail {
+                    detail.issue_tracker = detail.issue_tracker.next();
+                }
+            },
+            Message::IssueIdEdited(value) => {
+                if let Some(detail) = &mut self.ui.history.detail {
+                    detail.issue_id = value;
+                }
+            },
+            Message::IssueUrlEdited(value) => {
+                if let Some(detail) = &mut self.ui.history.detail {
+                    detail.issue_url = value;
+                }
+            },
+            Message::IssueLinked => {
+                let detail = match &mut self.ui.history.detail {
+                    Some(detail) => detail,
+                    None => return Command::none(),
+                };
+
+                if detail.issue_id.trim().is_empty() {
+                    detail.error = Some(tr!("detail.issue-id-missing").into());
+                    return Command::none();
+                }
+
+                let mut issue = IssueRef {
+                    tracker: detail.issue_tracker,
+                    id: detail.issue_id.trim().to_string(),
+                    url: detail.issue_url.trim().to_string(),
+                    status: IssueStatus::Open,
+                };
+
+                // joining a known issue keeps its status, and its url unless a new one is given
+                let known = self.data.as_ref()
+                    .and_then(|data| data.known_issues().into_iter().find(|known| known.issue.same_issue(&issue)));
+                if let Some(known) = known {
+                    issue.status = known.issue.status;
+                    if issue.url.is_empty() {
+                        issue.url = known.issue.url;
+                    }
+                }
+
+                detail.error = None;
+                detail.issue_id.clear();
+                detail.issue_url.clear();
+                return self.link_issue(issue);
+            },
+            Message::KnownIssueLinked(issue) => {
+                return self.link_issue(issue);
+            },
+            // the whole known issue changes, not only this record's copy
+            Message::IssueStatusCycled(issue) => {
+                if let Some(data) = &mut self.data {
+                    if data.set_issue_status(&issue, issue.status.next()) > 0 {
+                        return self.save_command();
+                    }
+                }
+            },
+            Message::IssueUnlinked(index) => {
+                let record_id = match &self.ui.history.detail {
+                    Some(detail) => detail.record_id.clone(),
+                    None => return Command::none(),
+                };
+
+                let removed = self.data.as_mut()
+                    .and_then(|data| data.record_mut(&record_id))
+                    .filter(|record| index < record.issues.len())
+                    .map(|record| record.issues.remove(index));
+
+                if removed.is_some() {
+                    return self.save_command();
+                }
+            },
+            Message::AttachmentRemoved(index) => {
+                let record_id = match &self.ui.history.detail {
+                    Some(detail) => detail.record_id.clone(),
+                    None => return Command::none(),
+                };
+
+                let attachment = self.data.as_mut()
+                    .and_then(|data| data.record_mut(&record_id))
+                    .filter(|record| index < record.attachments.len())
+                    .map(|record| record.attachments.remove(index));
+
+                if let Some(attachment) = attachment {
+                    return Command::batch(vec![
+                        Command::perform(attachments::remove(record_id, attachment), Message::AttachmentsCleaned),
+                        self.save_command(),
+                    ]);
+                }
+            },
+            Message::TracePathEdited(value) => {
+                if let Some(detail) = &mut self.ui.history.detail {
+                    detail.trace_path = value;
+                }
+            },
+            Message::TraceLoadClicked => {
+                if let Some(detail) = &mut self.ui.history.detail {
+                    let record_id = detail.record_id.clone();
+                    let source = PathBuf::from(detail.trace_path.trim());
+
+                    detail.error = None;
+                    return Command::perform(
+                        stack_trace::load(source),
+                        move |result| Message::TraceLoaded(record_id.clone(), result));
+                }
+            },
+            Message::TraceTextEdited(value) => {
+                if let Some(detail) = &mut self.ui.history.detail {
+                    detail.trace_text = value;
+                }
+            },
+            Message::TraceParseClicked => {
+                if let Some(detail) = &mut self.ui.history.detail {
+                    let record_id = detail.record_id.clone();
+                    let result = stack_trace::pasted(&detail.trace_text);
+
+                    detail.error = None;
+                    return self.update(Message::TraceLoaded(record_id, result));
+                }
+            },
+            Message::TraceLoaded(record_id, Ok(trace)) => {
+                if let Some(record) = self.data.as_mut().and_then(|data| data.record_mut(&record_id)) {
+                    record.set_stack_trace(trace);
+                    if let Some(detail) = &mut self.ui.history.detail {
+                        detail.trace_path.clear();
+                        detail.trace_text.clear();
+                    }
+                    return self.save_command();
+                }
+            },
+            Message::TraceLoaded(_, Err(err)) => {
+                if let Some(detail) = &mut self.ui.history.detail {
+                    detail.error = Some(match err {
+                        TraceError::FileError => tr!("attach.read-error").into(),
+                        TraceError::TooLarge => tr!("trace.too-large", size = stack_trace::MAX_TRACE_SIZE / 1024),
+                        TraceError::NoFrames => tr!("trace.no-frames").into(),
+                    });
+                }
+            },
+            Message::TraceRemoved => {
+                let record_id = match &self.ui.history.detail {
+                    Some(detail) => detail.record_id.clone(),
+                    None => return Command::none(),
+                };
+
+                if let Some(record) = self.data.as_mut().and_then(|data| data.record_mut(&record_id)) {
+                    record.stack_trace = None;
+                    return self.save_command();
+                }
+            },
+            Message::RecordDeleted => {
+                if let Some(detail) = self.ui.history.detail.take() {
+                    if let Some(ref mut data) = &mut self.data {
+                        data.records.retain(|record| record.id != detail.record_id);
+                    }
+                    return Command::batch(vec![
+                        Command::perform(attachments::remove_all(vec![detail.record_id]), Message::AttachmentsCleaned),
+                        self.save_command(),
+                    ]);
+                }
+            },
+            Message::AttachmentsCleaned(()) => (),
+            Message::ExportPathEdited(value) => {
+                self.ui.history.export.path = value;
+            },
+            Message::ExportClicked => {
+                let dest = self.ui.history.export.path.trim();
+                if let (Some(data), false) = (&self.data, dest.is_empty()) {
+                    let dest = PathBuf::from(dest);
+                    self.ui.history.export.report = None;
+                    return Command::perform(export::export(data.clone(), dest), Message::Exported);
+                }
+            },
+            Message::Exported(Ok(report)) => {
+                let mut text = trn!("export.report", report.records);
+                if !report.missing.is_empty() {
+                    text = trn!("export.missing", report.missing.len(),
+                                text = text,
+                                files = report.missing.join(", "));
+                }
+                self.ui.history.export.report = Some(text);
+            },
+            Message::Exported(Err(_)) => {
+                self.ui.history.export.report = Some(tr!("export.failed").into());
+            },
+            Message::SearchEdited(value) => {
+                self.ui.history.search = value;
+            },
+            Message::CheatSheetToggled => {
+                self.ui.cheat_sheet = !self.ui.cheat_sheet;
+            },
+            Message::IpcReceived(request) => {
+                return self.run_ipc_command(request);
+            },
+            Message::SnapshotCaptured(record_id, captured) => {
+                let record = match self.data.as_mut().and_then(|data| data.record_mut(&record_id)) {
+                    Some(record) => {
+                        record.snapshot = captured;
+                        record.clone()
+                    },
+                    None => return Command::none(),
+                };
+
+                return Command::batch(vec![self.announce_record(&record), self.save_command()]);
+            },
+            Message::HookFinished(outcome) => {
+                self.ui.hook_log.insert(0, outcome);
+                self.ui.hook_log.truncate(HOOK_LOG_LEN);
+            },
+            Message::OutboxLoaded(mut outbox) => {
+                // records may have been committed while it was loading
+                outbox.deliveries.append(&mut self.outbox.deliveries);
+                self.outbox = outbox;
+                return self.deliver_webhooks();
+            },
+            Message::OutboxSaved(_) => (),
+            Message::WebhooksDelivered(results) => {
+                self.delivering = false;
+                self.outbox.settle(results, Utc::now());
+                return Command::perform(self.outbox.clone().save(), Message::OutboxSaved);
+            },
+            Message::HttpReceived(request) => {
+                let data = match &mut self.data {
+                    Some(data) => data,
+                    None => {
+                        request.reply(HttpResponse::error(503, "records are still loading"));
+                        return Command::none();
+                    }
+                };
+
+                let handled = http_api::handle(data, &request, Utc::now());
+                request.reply(handled.response);
+
+                if handled.changed {
+                    return Command::batch(vec![
+                        Command::perform(attachments::remove_all(handled.deleted), Message::AttachmentsCleaned),
+                        self.save_command(),
+                    ]);
+                }
+            },
+        }
+
+        Command::none()
+    }
+
+    fn view(&mut self) -> UiElement!() {
+        // the one being picked on the settings screen, so it can be judged there
+        let theme = self.ui.settings.as_ref()
+            .and_then(|form| form.theme)
+            .unwrap_or(self.settings.theme);
+        let builder = UiBuilder::new(theme, self.settings.font_size);
+
+        if let Some(draft) = self.ui.draft.as_mut() {
+            let rows = Self::draft_rows(&builder, draft, &self.settings);
+            return if self.settings.compact { builder.strip(self.ui.layout_debug, rows) }
+                   else { builder.root(self.ui.layout_debug, rows) };
+        }
+
+        if self.settings.compact {
+            let now = self.ui.last_tick.unwrap_or_else(Utc::now);
+            let items = Self::compact_items(&builder, &mut self.ui.edit, now);
+            return builder.strip(self.ui.layout_debug, items);
+        }
+
+        if self.ui.cheat_sheet {
+            let rows = Self::cheat_sheet_rows(&builder,
+                                              &self.ui.shortcuts,
+                                              &mut self.ui.cheat_sheet_close_state);
+            return builder.root(self.ui.layout_debug, rows);
+        }
+
+        if let Some(detail) = self.ui.history.detail.as_mut() {
+            let record = self.data.as_ref().and_then(|data| data.record(&detail.record_id));
+            let known_issues = self.data.as_ref().map(AppData::known_issues).unwrap_or_default();
+            let rows = Self::detail_rows(&builder, detail, record, &known_issues, self.settings.time_display);
+            return builder.root(self.ui.layout_debug, rows);
+        }
+
+        let mut rows = vec![
+            Self::screen_tabs(&builder, &mut self.ui.screen_states, self.settings.screen),
+            builder.section_vspacer(),
+        ];
+
+        if let Some(warning) = &self.ui.settings_warning {
+            rows.push(builder.fill_row(
+                builder.label(warning.as_str()),
+                vec![builder.button(&mut self.ui.settings_warning_state,
+                                    tr!("common.close"),
+                                    ButtonStyle::Secondary,
+                                    Message::SettingsWarningClosed)]));
+            rows.push(builder.section_vspacer());
+        }
+
+        let records = self.data.as_ref().map_or(&[][..], |data| &data.records[..]);
+        let known_tags = self.data.as_ref().map(AppData::tags).unwrap_or_default();
+        let known_issues = self.data.as_ref().map(AppData::known_issues).unwrap_or_default();
+
+        match self.settings.screen {
+            Screen::Record => {
+                let now = self.ui.last_tick.unwrap_or_else(Utc::now);
+                let running = self.ui.edit.record.application.as_ref()
+                    .and_then(|application| self.data.as_ref()?.open_session(application))
+                    .map(|session| session.started);
+                rows.extend(Self::record_rows(&builder,
+                                              &mut self.ui.edit,
+                                              &self.settings.activities(),
+                                              &known_tags,
+                                              self.settings.capture_snapshot,
+                                              running,
+                                              now));
+
+                if !self.ui.hook_log.is_empty() {
+                    rows.push(builder.section_vspacer());
+                    rows.push(builder.title(tr!("hooks.title")));
+                    rows.push(builder.item_vspacer());
+                    for outcome in self.ui.hook_log.iter() {
+                        rows.push(builder.label(Self::hook_outcome_text(outcome, self.settings.time_display)));
+                        rows.push(builder.list_vspacer());
+                    }
+                }
+
+                if !self.ui.spool_log.is_empty() {
+                    rows.push(builder.section_vspacer());
+                    rows.push(builder.two_col_row(
+                        vec![builder.title(tr!("spool.title"))],
+                        vec![builder.button(&mut self.ui.spool_clear_state,
+                                            tr!("spool.clear"),
+                                            ButtonStyle::Secondary,
+                                            Message::SpoolLogCleared)],
+                        ColumnAlignment::Outward));
+                    rows.push(builder.item_vspacer());
+                    for outcome in self.ui.spool_log.iter() {
+                        rows.push(builder.label(Self::spool_outcome_text(outcome, self.settings.time_display)));
+                        rows.push(builder.list_vspacer());
+                    }
+                }
+            },
+            Screen::History => {
+                let unsaved = !self.settings.autosave && self.unsaved_since.is_some();
+                let now = self.ui.last_tick.unwrap_or_else(Utc::now);
+                let cluster = self.ui.history.cluster_filter.as_ref()
+                    .and_then(|id| clusters::compute(records, now).into_iter().find(|cluster| cluster.id == *id));
+                rows.extend(Self::history_rows(&builder,
+                                               &mut self.ui.history,
+                                               records,
+                                               &known_tags,
+                                               &known_issues,
+                                               cluster.as_ref(),
+                                               &self.ui.local_origin,
+                                               self.settings.time_display,
+                                               unsaved));
+            },
+            Screen::Signatures => {
+                let now = self.ui.last_tick.unwrap_or_else(Utc::now);
+                rows.extend(Self::signatures_rows(&builder,
+                                                  &mut self.ui.signatures,
+                                                  &clusters::compute(records, now),
+                                                  &self.settings));
+            },
+            Screen::Statistics => {
+                let now = self.ui.last_tick.unwrap_or_else(Utc::now);
+                let sessions = self.data.as_ref().map_or(&[][..], |data| &data.sessions[..]);
+                rows.extend(Self::stats_rows(&builder,
+                                             &mut self.ui.stats,
+                                             &Stats::compute(records, sessions, now),
+                                             &self.settings));
+            },
+            Screen::Applications => {
+                let now = self.ui.last_tick.unwrap_or_else(Utc::now);
+                rows.extend(Self::applications_rows(&builder,
+                                                    &mut self.ui.applications,
+                                                    self.data.as_ref(),
+                                                    &self.settings.watched,
+                                                    now));
+            },
+            Screen::Settings => {
+                if let Some(form) = self.ui.settings.as_mut() {
+                    rows.extend(Self::settings_rows(&builder, form, theme));
+                }
+
+                rows.push(builder.section_vspacer());
+                rows.push(builder.two_col_row(
+                    vec![],
+                    vec![builder.button(&mut self.ui.shortcuts_state,
+                                        tr!("shortcuts.open"),
+                                        ButtonStyle::Secondary,
+                                        Message::CheatSheetToggled)],
+                    ColumnAlignment::Right));
+            },
+        }
+
+        builder.root(self.ui.layout_debug, rows)
+    }
+}
+
+impl MainWindow {
+
+    fn register_entry(&mut self) -> Command<UiMessage!()>
+    {
+        if self.data.is_none() {
+            return Command::none();
+        }
+
+        let edit = std::mem::take(&mut self.ui.edit);
+        self.ui.edit.record.what = self.settings.default_what.clone();
+
+        Command::batch(vec![
+            self.commit_record(edit.record),
+            self.save_draft(),
+        ])
+    }
+
+    // a finished record, from the form or reported by another program
+    fn commit_record(&mut self, mut record: Record) -> Command<UiMessage!()> {
+        let mut commands = vec![];
+
+        if let Some(data) = self.data.as_mut() {
+            record.stamp_origin();
+
+            // the one running when nothing was picked and there is no doubt
+            if record.application.is_none() {
+                let mut open = data.sessions.iter().filter(|session| session.is_open());
+                if let (Some(session), None) = (open.next(), open.next()) {
+                    record.application = Some(session.application.clone());
+                }
+            }
+
+            // the crash or kill is the end of the run
+            if let Some(application) = record.application.clone() {
+                record.session_started = data.end_session(&application, record.when)
+                    .map(|session| session.started);
+            }
+
+            data.records.push(record.clone());
+            data.records.sort_by_key(|record| record.when);
+
+            // hooks and webhooks get the record once the snapshot is attached
+            if self.settings.capture_snapshot {
+                let record_id = record.id.clone();
+                commands.push(Command::perform(snapshot::capture(),
+                                               move |captured| Message::SnapshotCaptured(record_id.clone(), captured)));
+            }
+            else {
+                commands.push(self.announce_record(&record));
+            }
+        };
+
+        commands.push(self.save_command());
+
+        Command::batch(commands)
+    }
+
+    // to the hooks and webhooks
+    fn announce_record(&mut self, record: &Record) -> Command<UiMessage!()> {
+        let mut commands: Vec<_> = self.settings.hooks.iter()
+            .map(|hook| Command::perform(hooks::run(hook.clone(), record.clone()),
+                                         Message::HookFinished))
+            .collect();
+
+        if self.outbox.enqueue(&self.settings.webhooks, record, Utc::now()) {
+            commands.push(Command::perform(self.outbox.clone().save(), Message::OutboxSaved));
+        }
+
+        commands.push(self.deliver_webhooks());
+
+        Command::batch(commands)
+    }
+
+    // one batch at a time, the next tick picks up what became due meanwhile
+    fn deliver_webhooks(&mut self) -> Command<UiMessage!()> {
+        let due = self.outbox.due(Utc::now());
+
+        if self.delivering || due.is_empty() {
+            return Command::none();
+        }
+
+        self.delivering = true;
+        Command::perform(webhooks::deliver(due), Message::WebhooksDelivered)
+    }
+
+    // to the record open in the detail view
+    fn link_issue(&mut self, issue: IssueRef) -> Command<UiMessage!()> {
+        let record_id = match &self.ui.history.detail {
+            Some(detail) => detail.record_id.clone(),
+            None => return Command::none(),
+        };
+
+        let linked = self.data.as_mut()
+            .and_then(|data| data.record_mut(&record_id))
+            .map_or(false, |record| record.link_issue(issue));
+
+        if linked { self.save_command() } else { Command::none() }
+    }
+
+    fn start_session(&mut self, application: &str, source: SessionSource) -> bool {
+        self.data.as_mut().map_or(false, |data| data.start_session(application, source, Utc::now()))
+    }
+
+    fn scan_watched(&mut self, now: DateTime<Utc>) -> Command<UiMessage!()> {
+        let due = self.last_scan.map_or(true, |last| now - last >= Duration::seconds(watcher::SCAN_INTERVAL_SECS));
+
+        if self.settings.watched.is_empty() || self.data.is_none() || !due {
+            return Command::none();
+        }
+
+        self.last_scan = Some(now);
+        Command::perform(watcher::scan(self.settings.watched.clone()), Message::WatcherScanned)
+    }
+
+    fn collect_spool(&mut self, now: DateTime<Utc>) -> Command<UiMessage!()> {
+        let due = self.last_spool.map_or(true, |last| now - last >= Duration::seconds(spool::SCAN_INTERVAL_SECS));
+
+        if self.data.is_none() || self.spooling || !due {
+            return Command::none();
+        }
+
+        self.last_spool = Some(now);
+        self.spooling = true;
+        Command::perform(spool::collect(), Message::SpoolCollected)
+    }
+
+    fn is_typing(&self) -> bool {
+        let ui = &self.ui;
+        let mut inputs = vec![&ui.edit.description_state, &ui.edit.tag_state, &ui.applications.name_state];
+        inputs.extend(ui.history.text_inputs());
+        if let Some(form) = &ui.settings {
+            inputs.extend(form.text_inputs());
+        }
+
+        inputs.iter().any(|state| state.is_focused())
+    }
+
+    fn run_action(&mut self, action: Action) -> Command<UiMessage!()> {
+        match action {
+            Action::Crash => self.update(Message::CrashClicked),
+            Action::Kill => self.update(Message::KilledClicked),
+            Action::ToggleFrozen => self.update(Message::FrozenToggled(self.ui.edit.record.frozen.is_none())),
+            Action::ToggleBusy => self.update(Message::BusyToggled(self.ui.edit.record.busy.is_none())),
+            Action::Typing => self.update(Message::WhatChanged(WhatWasHappening::Typing)),
+            Action::Running => self.update(Message::WhatChanged(WhatWasHappening::Running)),
+            Action::Testing => self.update(Message::WhatChanged(WhatWasHappening::Testing)),
+            Action::Debugging => self.update(Message::WhatChanged(WhatWasHappening::Debugging)),
+            Action::FocusSearch => {
+                let command = self.update(Message::ScreenSelected(Screen::History));
+                self.ui.history.search_state = text_input::State::focused();
+                command
+            },
+            Action::Back => {
+                if self.ui.cheat_sheet {
+                    self.ui.cheat_sheet = false;
+                }
+                else if self.ui.history.detail.is_some() {
+                    self.ui.history.detail = None;
+                }
+                else if self.ui.history.search_state.is_focused() {
+                    self.ui.history.search.clear();
+                    self.ui.history.search_state = text_input::State::new();
+                }
+                Command::none()
+            },
+            Action::ToggleCheatSheet => self.update(Message::CheatSheetToggled),
+            Action::ToggleCompact => self.update(Message::CompactToggled),
+            Action::ToggleLayoutDebug => {
+                self.ui.layout_debug = !self.ui.layout_debug;
+                Command::none()
+            },
+        }
+    }
+
+    // commands are spelled after the messages the window already handles
+    fn run_ipc_command(&mut self, request: IpcRequest) -> Command<UiMessage!()> {
+        let ok = serde_json::json!({ "ok": true });
+
+        let command = match request.command.clone() {
+            IpcCommand::StartFreeze if self.ui.edit.record.frozen.is_none() =>
+                self.update(Message::FrozenToggled(true)),
+            IpcCommand::StartBusy if self.ui.edit.record.busy.is_none() =>
+                self.update(Message::BusyToggled(true)),
+            IpcCommand::StartFreeze | IpcCommand::StartBusy => Command::none(),
+            IpcCommand::RecordCrash => self.update(Message::CrashClicked),
+            IpcCommand::RecordKill => self.update(Message::KilledClicked),
+            IpcCommand::SetWhat { what } => self.update(Message::WhatChanged(what)),
+            IpcCommand::SetDescription { description } => self.update(Message::DescriptionEdited(description)),
+            IpcCommand::AppStarted { application } => {
+                let started = self.start_session(application.trim(), SessionSource::Cli);
+                request.reply(serde_json::json!({ "ok": true, "started": started }));
+                return if started { self.save_command() } else { Command::none() };
+            },
+            IpcCommand::AppStopped { application } => {
+                let stopped = self.data.as_mut()
+                    .and_then(|data| data.end_session(application.trim(), Utc::now()))
+                    .is_some();
+                request.reply(serde_json::json!({ "ok": true, "stopped": stopped }));
+                return if stopped { self.save_command() } else { Command::none() };
+            },
+            IpcCommand::Report(input) => {
+                let mut record = Record { when: Utc::now(), ..Record::default() };
+                if let Err(message) = input.apply(&mut record) {
+                    request.reply(serde_json::json!({ "ok": false, "error": message }));
+                    return Command::none();
+                }
+
+                let exists = match &self.data {
+                    Some(data) => {
+                        record.stamp_origin();
+                        data.record(&record.id).is_some()
+                    },
+                    None => {
+                        request.reply(serde_json::json!({ "ok": false, "error": "records are still loading" }));
+                        return Command::none();
+                    },
+                };
+
+                if exists {
+                    request.reply(serde_json::json!({ "ok": false, "error": "a record already exists at this time" }));
+                    return Command::none();
+                }
+
+                request.reply(serde_json::json!({ "ok": true, "id": record.id }));
+                return self.commit_record(record);
+            },
+            IpcCommand::QueryStats => {
+                let (records, sessions) = match &self.data {
+                    Some(data) => (&data.records[..], &data.sessions[..]),
+                    None => (&[][..], &[][..]),
+                };
+                let stats = Stats::compute(records, sessions, Utc::now());
+                request.reply(serde_json::json!({ "ok": true, "stats": stats }));
+                return Command::none();
+            },
+        };
+
+        request.reply(ok);
+
+        command
+    }
+
+    // called after each change: written right away, or later by `autosave`
+    // or the "Save" button depending on the settings
+    fn save_command(&mut self) -> Command<UiMessage!()>
+    {
+        self.changes += 1;
+
+        if self.settings.autosave && self.settings.autosave_delay_secs == 0 {
+            return self.write_data();
+        }
+
+        self.unsaved_since.get_or_insert_with(Utc::now);
+        Command::none()
+    }
+
+    fn write_data(&mut self) -> Command<UiMessage!()>
+    {
+        match &mut self.data {
+            Some(ref mut data) => {
+                self.unsaved_since = None;
+                // a file we couldn't read is compared against as if empty
+                let base = self.disk.clone().unwrap_or_else(|| OnDisk { path: AppData::path(), ..OnDisk::default() });
+                let sent = data.clone();
+                let changes = self.changes;
+                Command::perform(
+                    data.clone().save(base, self.settings.sync_dir.clone()),
+                    move |result| Message::Saved(sent.clone(), changes, result))
+            },
+            None => Command::none()
+        }
+    }
+
+    // records.json edited by a script or written by another instance
+    fn check_disk(&mut self, now: DateTime<Utc>) -> Command<UiMessage!()> {
+        let due = self.last_disk_check.map_or(true, |last| now - last >= Duration::seconds(app_data::RELOAD_INTERVAL_SECS));
+
+        let (disk, ours) = match (&self.disk, &self.data) {
+            (Some(disk), Some(data)) if !self.checking_disk && due => (disk.clone(), data.clone()),
+            _ => return Command::none(),
+        };
+
+        self.last_disk_check = Some(now);
+        self.checking_disk = true;
+        let changes = self.changes;
+        Command::perform(AppData::read_if_changed(disk, ours),
+                         move |changed| Message::DiskChanged(changes, changed))
+    }
+
+    fn autosave(&mut self, now: DateTime<Utc>) -> Command<UiMessage!()> {
+        let delay = Duration::seconds(self.settings.autosave_delay_secs as i64);
+
+        match self.unsaved_since {
+            Some(since) if self.settings.autosave && now - since >= delay => self.write_data(),
+            _ => Command::none(),
+        }
+    }
+
+    fn apply_settings(&mut self, settings: Settings) -> Command<UiMessage!()> {
+        let mut commands = vec![];
+        let moved = settings.data_dir != self.settings.data_dir;
+        let reload = moved || settings.sync_dir != self.settings.sync_dir;
+
+        // pending changes belong to the folder they were loaded from
+        if reload && self.unsaved_since.is_some() {
+            commands.push(self.write_data());
+        }
+
+        if settings.locale != self.settings.locale {
+            i18n::set_locale(settings.locale.unwrap_or_else(i18n::detect));
+        }
+
+        if settings.font_family != self.settings.font_family {
+            fonts::load(settings.font_family.as_deref());
+        }
+
+        if settings.shortcuts != self.settings.shortcuts {
+            self.ui.shortcuts = Shortcuts::new(&settings.shortcuts);
+        }
+
+        self.settings = settings;
+        self.ui.settings = Some(WindowUiSettingsState::new(&self.settings));
+        commands.push(Command::perform(self.settings.clone().save(), Message::SettingsSaved));
+
+        if moved {
+            AppData::set_data_dir(self.settings.data_dir.clone());
+            commands.push(Command::perform(self.outbox.clone().save(), Message::OutboxSaved));
+            commands.push(self.save_draft());
+        }
+
+        if reload {
+            commands.push(Command::perform(AppData::load(self.settings.sync_dir.clone()), Message::DataLoaded));
+        }
+
+        Command::batch(commands)
+    }
+
+    fn settings_rows<'a>(builder: &UiBuilder,
+                         state: &'a mut WindowUiSettingsState,
+                         theme: Theme) -> Vec<UiElement!(for<'a>)> {
+        let theme_radio = |value: Theme| builder.radio(value,
+                                                       value.label(),
+                                                       Some(theme),
+                                                       ButtonStyle::Secondary,
+                                                       Message::ThemeChanged);
+
+        let locale = state.locale;
+        let locale_radio = |value: Option<Locale>, label| builder.radio(value,
+                                                                       label,
+                                                                       Some(locale),
+                                                                       ButtonStyle::Secondary,
+                                                                       Message::LocaleChanged);
+        let language_row = builder.form_row(
+            builder.label(tr!("settings.language")),
+            builder.two_col_row(
+                vec![locale_radio(None, tr!("settings.language-auto"))],
+                Locale::ALL.iter()
+                    .map(|locale| locale_radio(Some(*locale), locale.label()))
+                    .collect(),
+                ColumnAlignment::Left));
+
+        let mut rows = vec![
+            builder.title(tr!("settings.title")),
+            builder.item_vspacer(),
+            language_row,
+            builder.item_vspacer(),
+            builder.two_col_row(
+                vec![theme_radio(Theme::Light), theme_radio(Theme::Dark)],
+                vec![theme_radio(Theme::HighContrast)],
+                ColumnAlignment::Left),
+            builder.item_vspacer(),
+            builder.form_row(
+                builder.label(tr!("settings.font")),
+                builder.input(&mut state.font_family_state,
+                              tr!("settings.font-default"),
+                              &state.font_family,
+                              Message::FontFamilyEdited)),
+            builder.list_vspacer(),
+            builder.form_row(
+                builder.label(tr!("settings.font-size")),
+                builder.input(&mut state.font_size_state,
+                              "18",
+                              &state.font_size,
+                              Message::FontSizeEdited)),
+            builder.section_vspacer(),
+            builder.form_row(
+                builder.label(tr!("settings.data-dir")),
+                builder.input(&mut state.data_dir_state,
+                              tr!("settings.data-dir-default"),
+                              &state.data_dir,
+                              Message::DataDirEdited)),
+            builder.list_vspacer(),
+            builder.form_row(
+                builder.label(tr!("settings.sync-dir")),
+                builder.input(&mut state.sync_dir_state,
+                              tr!("settings.sync-off"),
+                              &state.sync_dir,
+                              Message::SyncDirEdited)),
+            builder.list_vspacer(),
+            builder.form_row(
+                builder.label(tr!("settings.http-port")),
+                builder.input(&mut state.http_port_state,
+                              tr!("settings.http-off"),
+                              &state.http_port,
+                              Message::HttpPortEdited)),
+            builder.list_vspacer(),
+            builder.form_row(
+                builder.label(tr!("settings.tick-interval")),
+                builder.input(&mut state.tick_interval_state,
+                              "1000",
+                              &state.tick_interval,
+                              Message::TickIntervalEdited)),
+            builder.section_vspacer(),
+            builder.title(tr!("settings.saving")),
+            builder.item_vspacer(),
+            builder.checkbox(state.autosave,
+                             tr!("settings.autosave"),
+                             ButtonStyle::Secondary,
+                             Message::AutosaveToggled),
+            builder.item_vspacer(),
+            builder.form_row(
+                builder.label(tr!("settings.autosave-delay")),
+                builder.input(&mut state.autosave_delay_state,
+                              "0",
+                              &state.autosave_delay,
+                              Message::AutosaveDelayEdited)),
+            builder.section_vspacer(),
+            builder.title(tr!("settings.times")),
+            builder.item_vspacer(),
+            builder.two_col_row(
+                vec![builder.radio(TimeDisplay::Local,
+                                   tr!("settings.local-time"),
+                                   state.time_display,
+                                   ButtonStyle::Secondary,
+                                   Message::TimeDisplayChanged)],
+                vec![builder.radio(TimeDisplay::Utc,
+                                   tr!("settings.utc"),
+                                   state.time_display,
+                                   ButtonStyle::Secondary,
+                                   Message::TimeDisplayChanged)],
+                ColumnAlignment::Left),
+            builder.section_vspacer(),
+            builder.title(tr!("settings.compact")),
+            builder.item_vspacer(),
+            builder.label(tr!("settings.compact-resize")),
+            builder.item_vspacer(),
+            builder.checkbox(state.always_on_top,
+                             tr!("settings.always-on-top"),
+                             ButtonStyle::Secondary,
+                             Message::AlwaysOnTopToggled),
+            builder.section_vspacer(),
+            builder.title(tr!("settings.activities")),
+            builder.item_vspacer(),
+        ];
+
+        state.activity_states.resize_with(state.activities.len(), Default::default);
+
+        for (i, (activity, button_state)) in state.activities.iter()
+            .zip(state.activity_states.iter_mut())
+            .enumerate() {
+            rows.push(builder.fill_row(
+                builder.label(activity.label.as_str()),
+                vec![builder.button(button_state,
+                                    tr!("common.remove"),
+                                    ButtonStyle::Danger,
+                                    Message::ActivityRemoved(i))]));
+            rows.push(builder.list_vspacer());
+        }
+
+        rows.push(builder.fill_row(
+            builder.input(&mut state.activity_label_state,
+                          tr!("settings.activity-label"),
+                          &state.activity_label,
+                          Message::ActivityLabelEdited),
+            vec![builder.button(&mut state.add_activity_state,
+                                tr!("settings.add-activity"),
+                                ButtonStyle::Secondary,
+                                Message::ActivityAdded)]));
+        rows.push(builder.section_vspacer());
+        rows.push(builder.title(tr!("settings.default-what")));
+        rows.push(builder.item_vspacer());
+
+        // among the activities being edited, not the applied ones
+        let mut choices: Vec<(Option<WhatWasHappening>, &str)> = vec![(None, tr!("settings.no-default-what"))];
+        choices.extend(WhatWasHappening::BUILT_IN.iter()
+            .map(|what| (Some(what.clone()), built_in_label(what))));
+        choices.extend(state.activities.iter()
+            .map(|activity| (Some(WhatWasHappening::from(activity.name.clone())), activity.label.as_str())));
+
+        state.default_what_states.resize_with(choices.len(), Default::default);
+
+        for ((what, label), button_state) in choices.into_iter().zip(state.default_what_states.iter_mut()) {
+            let style = if what == state.default_what { ButtonStyle::Primary } else { ButtonStyle::Secondary };
+            rows.push(builder.button(button_state, label, style, Message::DefaultWhatChanged(what)));
+            rows.push(builder.list_vspacer());
+        }
+
+        for error in state.errors.iter() {
+            rows.push(builder.item_vspacer());
+            rows.push(builder.label(error.as_str()));
+        }
+
+        rows.push(builder.section_vspacer());
+
+        if state.moving {
+            rows.push(builder.label(tr!("settings.moving")));
+        }
+        else if let Some(settings) = &state.pending_move {
+            let to = AppData::resolve_data_dir(settings.data_dir.clone());
+            rows.push(builder.label(tr!("settings.move-warning",
+                                        from = AppData::data_dir().display(),
+                                        to = to.display())));
+            rows.push(builder.item_vspacer());
+            rows.push(builder.two_col_row(
+                vec![],
+                vec![builder.button(&mut state.cancel_state,
+                                    tr!("common.cancel"),
+                                    ButtonStyle::Secondary,
+                                    Message::SettingsReverted),
+                     builder.button(&mut state.switch_state,
+                                    tr!("settings.switch-only"),
+                                    ButtonStyle::Secondary,
+                                    Message::DataDirMoveChosen(false)),
+                     builder.button(&mut state.move_state,
+                                    tr!("settings.move"),
+                                    ButtonStyle::Primary,
+                                    Message::DataDirMoveChosen(true))],
+                ColumnAlignment::Right));
+        }
+        else {
+            rows.push(builder.two_col_row(
+                vec![],
+                vec![builder.button(&mut state.cancel_state,
+                                    tr!("common.cancel"),
+                                    ButtonStyle::Secondary,
+                                    Message::SettingsReverted),
+                     builder.button(&mut state.apply_state,
+                                    tr!("common.apply"),
+                                    ButtonStyle::Primary,
+                                    Message::SettingsApplied)],
+                ColumnAlignment::Right));
+        }
+
+        rows
+    }
+
+    fn screen_tabs<'a>(builder: &UiBuilder,
+                       states: &'a mut [button::State; 6],
+                       current: Screen) -> UiElement!(for<'a>) {
+        builder.tabs(Screen::ALL.iter()
+            .zip(states.iter_mut())
+            .map(|(screen, state)| (state, screen.label(), *screen == current, Message::ScreenSelected(*screen)))
+            .collect())
+    }
+
+    fn duration_text(d: Duration) -> String {
+        format!(
+            "{:02}:{:02}:{:02}",
+            d.num_hours(),
+            d.num_minutes(),
+            d.num_seconds())
+    }
+
+    // hours and minutes, for what can run for days
+    fn uptime_text(d: Duration) -> String {
+        tr!("common.uptime", hours = d.num_hours(), minutes = d.num_minutes() % 60)
+    }
+
+    fn per_hour_text(rate: Option<f64>) -> String {
+        rate.map_or_else(|| tr!("stats.no-use").into(), |rate| format!("{:.2}", rate))
+    }
+
+    fn record_rows<'a>(builder: &UiBuilder,
+                       state: &'a mut WindowUiEditState,
+                       activities: &[(WhatWasHappening, &'a str)],
+                       known_tags: &[String],
+                       capture_snapshot: bool,
+                       running: Option<DateTime<Utc>>,
+                       now: DateTime<Utc>) -> Vec<UiElement!(for<'a>)> {
+        let frozen_spent = match state.record.frozen {
+            Some(when) => format!(" {}", tr!("record.ago", duration = Self::duration_text(now - when))),
+            None => String::new()
+        };
+
+        let busy_spent = match state.record.busy {
+            Some(when) => format!(" {}", tr!("record.ago", duration = Self::duration_text(now - when))),
+            None => String::new()
+        };
+
+        // the most used ones until something is typed
+        let prefix = normalize_name(&state.tag).unwrap_or_default();
+        let suggestions: Vec<&String> = known_tags.iter()
+            .filter(|tag| tag.starts_with(&prefix) && !state.record.tags.contains(*tag))
+            .take(TAG_SUGGESTIONS)
+            .collect();
+
+        state.suggestion_states.resize_with(suggestions.len(), Default::default);
+        let suggestion_chips = builder.chips(suggestions.iter()
+            .zip(state.suggestion_states.iter_mut())
+            .map(|(tag, chip_state)| builder.chip(chip_state, tag, false, Message::TagAdded((*tag).clone())))
+            .collect());
+
+        state.tag_states.resize_with(state.record.tags.len(), Default::default);
+        let tag_chips = builder.chips(state.record.tags.iter()
+            .zip(state.tag_states.iter_mut())
+            .map(|(tag, chip_state)| builder.chip(chip_state,
+                                                  &tr!("record.remove-tag", tag = tag),
+                                                  true,
+                                                  Message::TagRemoved(tag.clone())))
+            .collect());
+
+        let running_for = match running {
+            Some(started) => tr!("record.running-for", duration = Self::uptime_text(now - started)),
+            None => String::new()
+        };
+
+        //
+        let crash_button = builder.button(
+            &mut state.crash_state,
+            tr!("record.crashed"),
+            ButtonStyle::Secondary,
+            Message::CrashClicked);
+
+        let killed_button = builder.button(
+            &mut state.killed_state,
+            tr!("record.killed"),
+            ButtonStyle::Secondary,
+            Message::KilledClicked);
+
+        // radios hold positions in `activities`, two per row
+        let current = state.record.what.as_ref()
+            .and_then(|what| activities.iter().position(|(known, _)| known == what));
+        let mut radios = activities.iter()
+            .enumerate()
+            .map(|(i, (_, label))| builder.radio(i,
+                                                 *label,
+                                                 current,
+                                                 ButtonStyle::Secondary,
+                                                 Message::ActivitySelected));
+
+        let mut rows = vec![
+            builder.title(tr!("record.title")),
+            builder.item_vspacer(),
+        ];
+
+        while let Some(left) = radios.next() {
+            rows.push(builder.two_col_row(vec![left], radios.next().into_iter().collect(), ColumnAlignment::Left));
+            rows.push(builder.item_vspacer());
+        }
+
+        rows.extend(vec![
+            builder.input(&mut state.description_state,
+                          tr!("record.description"),
+                          &state.record.description,
+                          Message::DescriptionEdited),
+            builder.item_vspacer(),
+            builder.fill_row(
+                builder.input(&mut state.tag_state,
+                              tr!("record.tag"),
+                              &state.tag,
+                              Message::TagEdited),
+                vec![builder.button(&mut state.add_tag_state,
+                                    tr!("record.add-tag"),
+                                    ButtonStyle::Secondary,
+                                    Message::TagAdded(state.tag.clone()))]),
+            builder.list_vspacer(),
+            suggestion_chips,
+            builder.list_vspacer(),
+            tag_chips,
+            builder.item_vspacer(),
+            builder.form_row(
+                builder.button(&mut state.application_state,
+                               state.record.application.as_deref().unwrap_or(tr!("record.no-application")),
+                               ButtonStyle::Secondary,
+                               Message::RecordApplicationCycled),
+                builder.label(running_for),
+            ),
+            builder.item_vspacer(),
+            builder.form_row(
+                builder.checkbox(state.record.frozen.is_some(),
+                                 tr!("record.frozen"),
+                                 ButtonStyle::Secondary,
+                                 Message::FrozenToggled),
+                builder.label(frozen_spent),
+            ),
+            builder.item_vspacer(),
+            builder.form_row(
+                builder.checkbox(state.record.busy.is_some(),
+                                 tr!("record.busy"),
+                                 ButtonStyle::Secondary,
+                                 Message::BusyToggled),
+                builder.label(busy_spent),
+            ),
+            builder.item_vspacer(),
+            builder.checkbox(capture_snapshot,
+                             tr!("record.snapshot"),
+                             ButtonStyle::Secondary,
+                             Message::SnapshotToggled),
+            builder.item_vspacer(),
+            builder.two_col_row(
+                vec![builder.button(&mut state.compact_state,
+                                    tr!("compact.open"),
+                                    ButtonStyle::Secondary,
+                                    Message::CompactToggled)],
+                vec![crash_button, killed_button],
+                ColumnAlignment::Outward),
+        ]);
+
+        rows
     }
 
-    fn save_command(&mut self) -> Command<UiMessage!()>
-    {
-        match &mut self.data {
-            Some(ref mut data) => Command::perform(data.clone().save(), Message::Saved),
-            None => Command::none()
+    fn compact_items<'a>(builder: &UiBuilder,
+                         state: &'a mut WindowUiEditState,
+                         now: DateTime<Utc>) -> Vec<UiElement!(for<'a>)> {
+        let spent = |since: Option<DateTime<Utc>>| since
+            .map(|when| Self::duration_text(now - when))
+            .unwrap_or_default();
+
+        vec![
+            builder.checkbox(state.record.frozen.is_some(),
+                             tr!("record.frozen"),
+                             ButtonStyle::Secondary,
+                             Message::FrozenToggled),
+            builder.action_hspacer(),
+            builder.label(spent(state.record.frozen)),
+            builder.action_hspacer(),
+            builder.checkbox(state.record.busy.is_some(),
+                             tr!("record.busy"),
+                             ButtonStyle::Secondary,
+                             Message::BusyToggled),
+            builder.action_hspacer(),
+            builder.label(spent(state.record.busy)),
+            builder.two_col_row(
+                vec![],
+                vec![builder.button(&mut state.crash_state,
+                                    tr!("record.crashed"),
+                                    ButtonStyle::Secondary,
+                                    Message::CrashClicked),
+                     builder.button(&mut state.killed_state,
+                                    tr!("record.killed"),
+                                    ButtonStyle::Secondary,
+                                    Message::KilledClicked),
+                     builder.button(&mut state.compact_state,
+                                    tr!("compact.expand"),
+                                    ButtonStyle::Flat,
+                                    Message::CompactToggled)],
+                ColumnAlignment::Right),
+        ]
+    }
+
+    // left alone while a previous draft waits for an answer, not to overwrite it
+    fn save_draft(&mut self) -> Command<UiMessage!()> {
+        if self.ui.draft.is_some() {
+            return Command::none();
+        }
+
+        self.draft_dirty = false;
+        Command::perform(draft::save(self.ui.edit.record.clone()), Message::DraftSaved)
+    }
+
+    fn draft_rows<'a>(builder: &UiBuilder,
+                      state: &'a mut WindowUiDraftState,
+                      settings: &Settings) -> Vec<UiElement!(for<'a>)> {
+        let record = &state.record;
+        let time_format = |d: DateTime<Utc>| Self::time_text(d, settings.time_display, "%Y-%m-%d %H:%M:%S");
+        let mut summary = vec![];
+
+        if let Some(when) = record.frozen {
+            summary.push(tr!("entry.frozen-from", time = time_format(when)));
+        }
+
+        if let Some(when) = record.busy {
+            summary.push(tr!("entry.busy-from", time = time_format(when)));
+        }
+
+        if let Some(what) = &record.what {
+            summary.push(settings.activity_label(what));
+        }
+
+        if !record.description.trim().is_empty() {
+            summary.push(format!("\"{}\"", record.description.trim()));
+        }
+
+        let buttons = builder.two_col_row(
+            vec![],
+            vec![builder.button(&mut state.discard_state,
+                                tr!("draft.discard"),
+                                ButtonStyle::Danger,
+                                Message::DraftDiscarded),
+                 builder.button(&mut state.finish_state,
+                                tr!("draft.finish"),
+                                ButtonStyle::Primary,
+                                Message::DraftFinished)],
+            ColumnAlignment::Right);
+
+        if settings.compact {
+            return vec![builder.label(summary.join(", ")), buttons];
+        }
+
+        let mut rows = vec![
+            builder.title(tr!("draft.title")),
+            builder.item_vspacer(),
+            builder.label(tr!("draft.found")),
+            builder.item_vspacer(),
+        ];
+
+        for line in summary {
+            rows.push(builder.label(line));
+            rows.push(builder.list_vspacer());
+        }
+
+        rows.push(builder.section_vspacer());
+        rows.push(buttons);
+
+        rows
+    }
+
+    // only the compact strip stays on top, the full window behaves normally
+    fn apply_on_top(&mut self) -> Command<UiMessage!()> {
+        let on_top = self.settings.compact && self.settings.always_on_top;
+
+        if self.ui.on_top == Some(on_top) {
+            return Command::none();
+        }
+
+        self.ui.on_top = Some(on_top);
+        Command::perform(on_top::set_always_on_top(self.title(), on_top), Message::OnTopApplied)
+    }
+
+    fn history_rows<'a>(builder: &UiBuilder,
+                        state: &'a mut WindowUiHistoryState,
+                        records: &'a [Record],
+                        known_tags: &[String],
+                        known_issues: &[KnownIssue],
+                        cluster: Option<&Cluster>,
+                        local_origin: &str,
+                        time_display: TimeDisplay,
+                        unsaved: bool) -> Vec<UiElement!(for<'a>)> {
+        state.entry_states.resize_with(records.len(), Default::default);
+
+        let mut right_part = vec![];
+
+        if unsaved {
+            right_part.push(builder.button(
+                &mut state.save_state,
+                tr!("history.save"),
+                ButtonStyle::Primary,
+                Message::SaveClicked));
+        }
+
+        if !records.is_empty() {
+            right_part.push(builder.button(
+                &mut state.clear_state,
+                tr!("history.clear"),
+                ButtonStyle::Danger,
+                Message::ClearClicked));
+        }
+
+        let search = state.search.trim().to_lowercase();
+        let tag_filter = &state.tag_filter;
+
+        // selected ones stay offered even when they are no longer among the most used
+        let filters: Vec<&String> = known_tags.iter()
+            .enumerate()
+            .filter(|(i, tag)| *i < TAG_FILTERS || tag_filter.contains(*tag))
+            .map(|(_, tag)| tag)
+            .collect();
+
+        state.tag_filter_states.resize_with(filters.len(), Default::default);
+        let filter_chips = builder.chips(filters.iter()
+            .zip(state.tag_filter_states.iter_mut())
+            .map(|(tag, chip_state)| builder.chip(chip_state,
+                                                  tag,
+                                                  tag_filter.contains(*tag),
+                                                  Message::TagFilterToggled((*tag).clone())))
+            .collect());
+
+        // known issues group their records, "unreported" gathers the others
+        let issue_filter = &state.issue_filter;
+        let unreported = records.iter().filter(|record| record.issues.is_empty()).count();
+        let mut issue_choices = vec![(IssueFilter::Unreported, tr!("history.unreported", count = unreported))];
+        issue_choices.extend(known_issues.iter()
+            .enumerate()
+            .filter(|(i, known)| *i < ISSUE_CHIPS
+                || issue_filter.as_ref().map_or(false, |filter| filter.is(&IssueFilter::Known(known.issue.clone()))))
+            .map(|(_, known)| (IssueFilter::Known(known.issue.clone()),
+                               tr!("history.known-issue",
+                                   issue = known.issue.name(),
+                                   status = known.issue.status.label(),
+                                   count = known.records))));
+
+        state.issue_filter_states.resize_with(issue_choices.len(), Default::default);
+        let issue_chips = builder.chips(issue_choices.into_iter()
+            .zip(state.issue_filter_states.iter_mut())
+            .map(|((filter, label), chip_state)| {
+                let selected = issue_filter.as_ref().map_or(false, |current| current.is(&filter));
+                builder.chip(chip_state, &label, selected, Message::IssueFilterToggled(filter))
+            })
+            .collect());
+
+        // the records of one signature, until unticked
+        let mut cluster_chips = vec![];
+        if let Some(cluster) = cluster {
+            cluster_chips.push(builder.chip(&mut state.cluster_filter_state,
+                                            &tr!("history.cluster", count = cluster.count()),
+                                            true,
+                                            Message::ClusterFilterCleared));
+        }
+
+        let mut rows = vec![
+            builder.two_col_row(
+                vec![builder.title(tr!("history.title", count = records.len()))],
+                right_part,
+                ColumnAlignment::Outward),
+            builder.item_vspacer(),
+            builder.input(&mut state.search_state,
+                          tr!("history.search"),
+                          &state.search,
+                          Message::SearchEdited),
+            builder.list_vspacer(),
+            filter_chips,
+            builder.list_vspacer(),
+            issue_chips,
+            builder.list_vspacer(),
+            builder.chips(cluster_chips),
+            builder.item_vspacer(),
+            if records.is_empty() {
+                builder.placeholder(tr!("history.empty"))
+            }
+            else {
+                builder.list(&mut state.records_scroll_state,
+                             records.iter()
+                                 .filter(|record| tag_filter.iter().all(|tag| record.tags.contains(tag)))
+                                 .filter(|record| cluster.map_or(true, |cluster| cluster.record_ids.contains(&record.id)))
+                                 .filter(|record| match issue_filter {
+                                     None => true,
+                                     Some(IssueFilter::Unreported) => record.issues.is_empty(),
+                                     Some(IssueFilter::Known(issue)) => record.issues.iter().any(|linked| linked.same_issue(issue)),
+                                 })
+                                 .filter(|record| search.is_empty()
+                                     || record.tags.iter().any(|tag| tag.contains(&search))
+                                     || Self::entry_text(record, local_origin, time_display)
+                                         .to_lowercase()
+                                         .contains(&search))
+                                 .zip(state.entry_states.iter_mut())
+                                 .map(|(record, state)| Self::make_entry(builder, state, record, local_origin, time_display))
+                                 .collect())
+            },
+        ];
+
+        rows.extend(Self::import_section(builder, &mut state.import));
+        rows.extend(Self::export_section(builder, &mut state.export));
+
+        rows
+    }
+
+    fn signatures_rows<'a>(builder: &UiBuilder,
+                           state: &'a mut WindowUiSignaturesState,
+                           clusters: &[Cluster],
+                           settings: &Settings) -> Vec<UiElement!(for<'a>)> {
+        let mut rows = vec![
+            builder.title(tr!("signatures.title", count = clusters.len())),
+            builder.item_vspacer(),
+        ];
+
+        if clusters.is_empty() {
+            rows.push(builder.placeholder(tr!("signatures.empty")));
+            return rows;
+        }
+
+        rows.push(builder.label(tr!("signatures.hint")));
+        rows.push(builder.item_vspacer());
+
+        state.cluster_states.resize_with(clusters.len(), Default::default);
+
+        let mut list = vec![];
+        for (cluster, cluster_state) in clusters.iter().zip(state.cluster_states.iter_mut()) {
+            let dates = tr!("signatures.dates",
+                            first = Self::time_text(cluster.first, settings.time_display, "%Y-%m-%d"),
+                            last = Self::time_text(cluster.last, settings.time_display, "%Y-%m-%d"),
+                            trend = Self::trend_text(cluster));
+
+            list.push(builder.list_item(cluster_state,
+                                        builder.label(Self::cluster_text(cluster, settings)),
+                                        Message::ClusterSelected(cluster.id.clone())));
+            list.push(builder.label(dates));
+            list.push(builder.list_vspacer());
+        }
+
+        rows.push(builder.list(&mut state.scroll_state, list));
+
+        rows
+    }
+
+    // "3× editor: crashed while typing: SIGSEGV"
+    fn cluster_text(cluster: &Cluster, settings: &Settings) -> String {
+        let mut text: String = match cluster.how {
+            HowItWasStopped::SelfCrashed => tr!("signatures.crashed").into(),
+            HowItWasStopped::ManuallyKilled => tr!("signatures.killed").into(),
+        };
+
+        if let Some(what) = &cluster.what {
+            text = tr!("signatures.while", text = text, activity = settings.activity_label(what));
+        }
+
+        if let Some(application) = &cluster.application {
+            text = tr!("signatures.in", text = text, application = application);
+        }
+
+        let details = match &cluster.signature {
+            Some(signature) => signature.clone(),
+            None => cluster.words.join(" "),
+        };
+
+        if !details.is_empty() {
+            text = tr!("signatures.details", text = text, details = details);
+        }
+
+        tr!("signatures.count", text = text, count = cluster.count())
+    }
+
+    fn trend_text(cluster: &Cluster) -> String {
+        let (now, before) = (cluster.this_week, cluster.last_week);
+
+        match cluster.trend() {
+            Trend::New => tr!("signatures.trend.new", now = now),
+            Trend::Rising => tr!("signatures.trend.rising", now = now, before = before),
+            Trend::Steady => tr!("signatures.trend.steady", now = now),
+            Trend::Falling => tr!("signatures.trend.falling", now = now, before = before),
+            Trend::Quiet => tr!("signatures.trend.quiet").into(),
+        }
+    }
+
+    fn stats_rows<'a>(builder: &UiBuilder,
+                      state: &'a mut WindowUiStatsState,
+                      stats: &Stats,
+                      settings: &Settings) -> Vec<UiElement!(for<'a>)> {
+        let count_row = |label: &str, count: usize| builder.form_row(
+            builder.label(label),
+            builder.label(count.to_string()));
+
+        let last = match stats.last {
+            Some(when) => Self::time_text(when, settings.time_display, "%Y-%m-%d %H:%M:%S"),
+            None => tr!("stats.never").into(),
+        };
+
+        // the configured activities, then those only known from records of another setup
+        let mut whats: Vec<(String, String)> = settings.activities().into_iter()
+            .map(|(what, label)| (what.name().to_string(), label.to_string()))
+            .collect();
+
+        for name in stats.by_what.keys() {
+            if name != "unspecified" && !whats.iter().any(|(known, _)| known == name) {
+                whats.push((name.clone(), settings.activity_label(&WhatWasHappening::from(name.clone()))));
+            }
+        }
+
+        whats.push(("unspecified".into(), tr!("stats.unspecified").into()));
+
+        let mut list: Vec<UiElement!(for<'a>)> = whats.iter()
+            .map(|(name, label)| count_row(label, stats.by_what.get(name).copied().unwrap_or(0)))
+            .collect();
+
+        if !stats.by_tag.is_empty() {
+            list.push(builder.section_vspacer());
+            list.push(builder.title(tr!("stats.by-tag")));
+            list.push(builder.item_vspacer());
+        }
+
+        for (tag, count) in stats.by_tag.iter() {
+            list.push(count_row(tag, *count));
+        }
+
+        if !stats.by_application.is_empty() {
+            list.push(builder.section_vspacer());
+            list.push(builder.title(tr!("stats.by-application")));
+            list.push(builder.item_vspacer());
+        }
+
+        for (application, figures) in stats.by_application.iter() {
+            let uptime = figures.mean_uptime_secs
+                .map_or_else(|| tr!("stats.unknown").into(), |secs| Self::uptime_text(Duration::seconds(secs)));
+            list.push(builder.label(trn!("stats.application",
+                                         figures.records,
+                                         application = application,
+                                         rate = Self::per_hour_text(figures.crashes_per_hour),
+                                         uptime = uptime)));
+            list.push(builder.list_vspacer());
+        }
+
+        vec![
+            builder.title(tr!("screen.statistics")),
+            builder.item_vspacer(),
+            count_row(tr!("stats.total"), stats.total),
+            builder.list_vspacer(),
+            count_row(tr!("stats.crashed"), stats.crashed),
+            builder.list_vspacer(),
+            count_row(tr!("stats.killed"), stats.killed),
+            builder.list_vspacer(),
+            count_row(tr!("stats.reported"), stats.reported),
+            builder.list_vspacer(),
+            count_row(tr!("stats.unreported"), stats.unreported),
+            builder.list_vspacer(),
+            builder.form_row(builder.label(tr!("stats.issues")),
+                             builder.label(tr!("stats.issues-open", open = stats.open_issues, total = stats.issues))),
+            builder.list_vspacer(),
+            count_row(tr!("stats.last-24h"), stats.last_24h),
+            builder.list_vspacer(),
+            count_row(tr!("stats.last-7d"), stats.last_7d),
+            builder.list_vspacer(),
+            builder.form_row(builder.label(tr!("stats.last")), builder.label(last)),
+            builder.list_vspacer(),
+            builder.form_row(builder.label(tr!("stats.use")),
+                             builder.label(Self::uptime_text(Duration::seconds(stats.use_secs)))),
+            builder.list_vspacer(),
+            builder.form_row(builder.label(tr!("stats.per-hour")),
+                             builder.label(Self::per_hour_text(stats.crashes_per_hour))),
+            builder.section_vspacer(),
+            builder.title(tr!("stats.by-what")),
+            builder.item_vspacer(),
+            builder.list(&mut state.scroll_state, list),
+        ]
+    }
+
+    fn applications_rows<'a>(builder: &UiBuilder,
+                             state: &'a mut WindowUiApplicationsState,
+                             data: Option<&AppData>,
+                             watched: &[WatchedApplication],
+                             now: DateTime<Utc>) -> Vec<UiElement!(for<'a>)> {
+        let mut applications = data.map(AppData::applications).unwrap_or_default();
+        for watched in watched.iter() {
+            if !applications.contains(&watched.application) {
+                applications.push(watched.application.clone());
+            }
+        }
+
+        let mut rows = vec![
+            builder.title(tr!("screen.applications")),
+            builder.item_vspacer(),
+            builder.fill_row(
+                builder.input(&mut state.name_state,
+                              tr!("applications.name"),
+                              &state.name,
+                              Message::ApplicationNameEdited),
+                vec![builder.button(&mut state.start_state,
+                                    tr!("applications.start"),
+                                    ButtonStyle::Primary,
+                                    Message::ApplicationStarted(state.name.trim().to_string()))]),
+            builder.section_vspacer(),
+        ];
+
+        if applications.is_empty() {
+            rows.push(builder.placeholder(tr!("applications.empty")));
+            return rows;
+        }
+
+        state.session_states.resize_with(applications.len(), Default::default);
+
+        let items = applications.iter()
+            .zip(state.session_states.iter_mut())
+            .map(|(application, button_state)| {
+                match data.and_then(|data| data.open_session(application)) {
+                    Some(session) => builder.fill_row(
+                        builder.label(tr!("applications.running",
+                                          application = application,
+                                          duration = Self::uptime_text(now - session.started),
+                                          source = Self::source_label(session.source))),
+                        vec![builder.button(button_state,
+                                            tr!("applications.stop"),
+                                            ButtonStyle::Secondary,
+                                            Message::ApplicationStopped(application.clone()))]),
+                    None => builder.fill_row(
+                        builder.label(tr!("applications.stopped", application = application)),
+                        vec![builder.button(button_state,
+                                            tr!("applications.start"),
+                                            ButtonStyle::Secondary,
+                                            Message::ApplicationStarted(application.clone()))]),
+                }
+            })
+            .collect();
+
+        rows.push(builder.list(&mut state.scroll_state, items));
+
+        rows
+    }
+
+    fn source_label(source: SessionSource) -> &'static str {
+        match source {
+            SessionSource::Manual => tr!("applications.source.manual"),
+            SessionSource::Cli => tr!("applications.source.cli"),
+            SessionSource::Watcher => tr!("applications.source.watcher"),
+        }
+    }
+
+    fn import_section<'a>(builder: &UiBuilder,
+                          state: &'a mut WindowUiImportState) -> Vec<UiElement!(for<'a>)> {
+        let mut rows = vec![
+            builder.section_vspacer(),
+            builder.title(tr!("import.title")),
+            builder.item_vspacer(),
+        ];
+
+        match &state.preview {
+            None => {
+                rows.push(builder.fill_row(
+                    builder.input(&mut state.path_state,
+                                  tr!("import.path"),
+                                  &state.path,
+                                  Message::ImportPathEdited),
+                    vec![builder.button(
+                        &mut state.open_state,
+                        tr!("import.open"),
+                        ButtonStyle::Secondary,
+                        Message::ImportOpenClicked)]));
+            },
+            Some(ImportPreview { kind: ImportKind::Csv { headers, mapping, .. }, .. }) => {
+                for (field, button_state) in CsvField::ALL.iter().zip(state.mapping_states.iter_mut()) {
+                    let column = mapping.get(*field)
+                        .and_then(|i| headers.get(i))
+                        .map_or(tr!("import.unmapped"), |header| header.as_str());
+
+                    rows.push(builder.form_row(
+                        builder.label(field.label()),
+                        builder.button(button_state,
+                                       column,
+                                       ButtonStyle::Secondary,
+                                       Message::ImportMappingCycled(*field))));
+                    rows.push(builder.list_vspacer());
+                }
+            },
+            Some(ImportPreview { kind: ImportKind::Recorder { count }, .. }) => {
+                rows.push(builder.label(trn!("import.found", *count)));
+            },
+        }
+
+        let rejected = state.preview.as_ref().map_or(vec![], |preview| preview.rejected_rows());
+        if !rejected.is_empty() {
+            let numbers: Vec<String> = rejected.iter().map(|row| row.to_string()).collect();
+            rows.push(builder.label(trn!("import.rejected", rejected.len(), rows = numbers.join(", "))));
+        }
+
+        if state.preview.is_some() {
+            rows.push(builder.item_vspacer());
+            rows.push(builder.two_col_row(
+                vec![],
+                vec![builder.button(&mut state.cancel_state,
+                                    tr!("common.cancel"),
+                                    ButtonStyle::Secondary,
+                                    Message::ImportCancelled),
+                     builder.button(&mut state.confirm_state,
+                                    tr!("import.confirm"),
+                                    ButtonStyle::Primary,
+                                    Message::ImportConfirmed)],
+                ColumnAlignment::Right));
+        }
+
+        if let Some(report) = &state.report {
+            rows.push(builder.item_vspacer());
+            rows.push(builder.label(report.as_str()));
+        }
+
+        rows
+    }
+
+    fn cheat_sheet_rows<'a>(builder: &UiBuilder,
+                            shortcuts: &Shortcuts,
+                            close_state: &'a mut button::State) -> Vec<UiElement!(for<'a>)> {
+        let mut rows = vec![
+            builder.title(tr!("shortcuts.title")),
+            builder.item_vspacer(),
+        ];
+
+        for (binding, action) in shortcuts.bindings() {
+            rows.push(builder.form_row(builder.label(binding.label()), builder.label(action.label())));
+            rows.push(builder.list_vspacer());
+        }
+
+        rows.push(builder.section_vspacer());
+        rows.push(builder.two_col_row(
+            vec![],
+            vec![builder.button(close_state,
+                                tr!("common.close"),
+                                ButtonStyle::Secondary,
+                                Message::CheatSheetToggled)],
+            ColumnAlignment::Right));
+
+        rows
+    }
+
+    fn export_section<'a>(builder: &UiBuilder,
+                          state: &'a mut WindowUiExportState) -> Vec<UiElement!(for<'a>)> {
+        let mut rows = vec![
+            builder.section_vspacer(),
+            builder.title(tr!("export.title")),
+            builder.item_vspacer(),
+            builder.fill_row(
+                builder.input(&mut state.path_state,
+                              tr!("export.path"),
+                              &state.path,
+                              Message::ExportPathEdited),
+                vec![builder.button(
+                    &mut state.export_state,
+                    tr!("export.button"),
+                    ButtonStyle::Secondary,
+                    Message::ExportClicked)]),
+        ];
+
+        if let Some(report) = &state.report {
+            rows.push(builder.item_vspacer());
+            rows.push(builder.label(report.as_str()));
+        }
+
+        rows
+    }
+
+    fn detail_rows<'a>(builder: &UiBuilder,
+                       state: &'a mut WindowUiDetailState,
+                       record: Option<&'a Record>,
+                       known_issues: &[KnownIssue],
+                       time_display: TimeDisplay) -> Vec<UiElement!(for<'a>)> {
+        let mut rows = vec![
+            builder.title(tr!("detail.title")),
+            builder.item_vspacer(),
+        ];
+
+        match record {
+            None => {
+                rows.push(builder.placeholder(tr!("detail.gone")));
+            },
+            Some(record) => {
+                rows.push(builder.tagged(builder.label(Self::entry_text(record, "", time_display)), &record.tags));
+                rows.push(builder.item_vspacer());
+                rows.push(builder.label(tr!(
+                    "detail.recorded",
+                    when = Self::time_text(record.when, time_display, "%Y-%m-%d %H:%M:%S"),
+                    origin = record.origin())));
+
+                if let Some(application) = &record.application {
+                    rows.push(builder.item_vspacer());
+                    rows.push(builder.label(match record.session_started {
+                        Some(started) => tr!("detail.application-uptime",
+                                             application = application,
+                                             duration = Self::uptime_text(record.when - started)),
+                        None => tr!("detail.application", application = application),
+                    }));
+                }
+
+                if let Some(signal) = &record.signal {
+                    rows.push(builder.item_vspacer());
+                    rows.push(builder.label(tr!("detail.signal", signal = signal)));
+                }
+
+                rows.push(builder.section_vspacer());
+                match &record.stack_trace {
+                    Some(trace) => {
+                        rows.push(builder.two_col_row(
+                            vec![builder.title(tr!("trace.title", format = Self::trace_format_label(trace.format)))],
+                            vec![builder.button(&mut state.remove_trace_state,
+                                                tr!("common.remove"),
+                                                ButtonStyle::Danger,
+                                                Message::TraceRemoved)],
+                            ColumnAlignment::Outward));
+                        rows.push(builder.item_vspacer());
+
+                        if !trace.message.is_empty() {
+                            rows.push(builder.label(trace.message.as_str()));
+                            rows.push(builder.item_vspacer());
+                        }
+
+                        for (i, frame) in trace.frames.iter().enumerate().take(SHOWN_FRAMES) {
+                            rows.push(builder.label(tr!("trace.frame", index = i, frame = frame.text())));
+                        }
+
+                        if trace.frames.len() > SHOWN_FRAMES {
+                            rows.push(builder.placeholder(tr!("trace.more", count = trace.frames.len() - SHOWN_FRAMES)));
+                        }
+
+                        if let Some(signature) = trace.signature() {
+                            rows.push(builder.item_vspacer());
+                            rows.push(builder.label(tr!("trace.signature", signature = signature)));
+                        }
+                    },
+                    None => {
+                        rows.push(builder.title(tr!("trace.none")));
+                        rows.push(builder.item_vspacer());
+                        rows.push(builder.fill_row(
+                            builder.input(&mut state.trace_path_state,
+                                          tr!("trace.path"),
+                                          &state.trace_path,
+                                          Message::TracePathEdited),
+                            vec![builder.button(&mut state.load_trace_state,
+                                                tr!("trace.load"),
+                                                ButtonStyle::Secondary,
+                                                Message::TraceLoadClicked)]));
+                        rows.push(builder.item_vspacer());
+                        rows.push(builder.fill_row(
+                            builder.input(&mut state.trace_text_state,
+                                          tr!("trace.text"),
+                                          &state.trace_text,
+                                          Message::TraceTextEdited),
+                            vec![builder.button(&mut state.parse_trace_state,
+                                                tr!("trace.parse"),
+                                                ButtonStyle::Secondary,
+                                                Message::TraceParseClicked)]));
+                    },
+                }
+
+                rows.push(builder.section_vspacer());
+                rows.push(builder.title(tr!("detail.attachments", count = record.attachments.len())));
+                rows.push(builder.item_vspacer());
+
+                if record.attachments.is_empty() {
+                    rows.push(builder.placeholder(tr!("detail.no-attachments")));
+                }
+
+                state.remove_states.resize_with(record.attachments.len(), Default::default);
+
+                for (i, (attachment, remove_state)) in record.attachments.iter()
+                    .zip(state.remove_states.iter_mut())
+                    .enumerate() {
+                    rows.push(builder.fill_row(
+                        builder.label(tr!("detail.attachment",
+                                          name = attachment.name,
+                                          size = (attachment.size + 1023) / 1024)),
+                        vec![builder.button(remove_state,
+                                            tr!("common.remove"),
+                                            ButtonStyle::Danger,
+                                            Message::AttachmentRemoved(i))]));
+                    rows.push(builder.list_vspacer());
+                }
+
+                rows.push(builder.item_vspacer());
+                rows.push(builder.fill_row(
+                    builder.input(&mut state.attachment_path_state,
+                                  tr!("detail.attachment-path"),
+                                  &state.attachment_path,
+                                  Message::AttachmentPathEdited),
+                    vec![builder.button(&mut state.attach_state,
+                                        tr!("detail.attach"),
+                                        ButtonStyle::Secondary,
+                                        Message::AttachClicked)]));
+
+                rows.push(builder.section_vspacer());
+                rows.push(builder.title(tr!("detail.issues", count = record.issues.len())));
+                rows.push(builder.item_vspacer());
+
+                if record.issues.is_empty() {
+                    rows.push(builder.placeholder(tr!("detail.no-issues")));
+                }
+
+                state.issue_states.resize_with(record.issues.len(), Default::default);
+
+                for (i, (issue, (status_state, remove_state))) in record.issues.iter()
+                    .zip(state.issue_states.iter_mut())
+                    .enumerate() {
+                    let text =
+                        if issue.url.is_empty() { issue.name() }
+                        else { tr!("detail.issue", issue = issue.name(), url = issue.url) };
+
+                    rows.push(builder.fill_row(
+                        builder.label(text),
+                        vec![builder.button(status_state,
+                                            issue.status.label(),
+                                            ButtonStyle::Secondary,
+                                            Message::IssueStatusCycled(issue.clone())),
+                             builder.button(remove_state,
+                                            tr!("common.remove"),
+                                            ButtonStyle::Danger,
+                                            Message::IssueUnlinked(i))]));
+                    rows.push(builder.list_vspacer());
+                }
+
+                rows.push(builder.item_vspacer());
+                rows.push(builder.fill_row(
+                    builder.input(&mut state.issue_id_state,
+                                  tr!("detail.issue-id"),
+                                  &state.issue_id,
+                                  Message::IssueIdEdited),
+                    vec![builder.button(&mut state.tracker_state,
+                                        state.issue_tracker.label(),
+                                        ButtonStyle::Secondary,
+                                        Message::IssueTrackerCycled)]));
+                rows.push(builder.list_vspacer());
+                rows.push(builder.fill_row(
+                    builder.input(&mut state.issue_url_state,
+                                  tr!("detail.issue-url"),
+                                  &state.issue_url,
+                                  Message::IssueUrlEdited),
+                    vec![builder.button(&mut state.link_state,
+                                        tr!("detail.link"),
+                                        ButtonStyle::Secondary,
+                                        Message::IssueLinked)]));
+
+                // one click to add this record to a known issue
+                let others: Vec<&KnownIssue> = known_issues.iter()
+                    .filter(|known| !record.issues.iter().any(|linked| linked.same_issue(&known.issue)))
+                    .take(ISSUE_CHIPS)
+                    .collect();
+
+                if !others.is_empty() {
+                    state.known_issue_states.resize_with(others.len(), Default::default);
+                    rows.push(builder.list_vspacer());
+                    rows.push(builder.chips(others.iter()
+                        .zip(state.known_issue_states.iter_mut())
+                        .map(|(known, chip_state)| builder.chip(chip_state,
+                                                                &tr!("detail.link-known", issue = known.issue.name()),
+                                                                false,
+                                                                Message::KnownIssueLinked(known.issue.clone())))
+                        .collect()));
+                }
+
+                if let Some(error) = &state.error {
+                    rows.push(builder.item_vspacer());
+                    rows.push(builder.label(error.as_str()));
+                }
+
+                if let Some(snapshot) = &record.snapshot {
+                    rows.push(builder.section_vspacer());
+                    rows.push(builder.title(tr!("detail.system")));
+                    rows.push(builder.item_vspacer());
+                    for line in Self::snapshot_lines(snapshot) {
+                        rows.push(builder.label(line));
+                    }
+                }
+            },
+        }
+
+        let delete_button =
+            if record.is_some() {
+                vec![builder.button(&mut state.delete_state,
+                                    tr!("common.delete"),
+                                    ButtonStyle::Danger,
+                                    Message::RecordDeleted)]
+            }
+            else { vec![] };
+
+        rows.push(builder.section_vspacer());
+        rows.push(builder.two_col_row(
+            vec![builder.button(&mut state.back_state,
+                                tr!("common.back"),
+                                ButtonStyle::Secondary,
+                                Message::DetailClosed)],
+            delete_button,
+            ColumnAlignment::Outward));
+
+        rows
+    }
+
+    fn trace_format_label(format: TraceFormat) -> &'static str {
+        match format {
+            TraceFormat::RustPanic => tr!("trace.format.rust"),
+            TraceFormat::Gdb => tr!("trace.format.gdb"),
+            TraceFormat::Java => tr!("trace.format.java"),
+        }
+    }
+
+    fn snapshot_lines(snapshot: &SystemSnapshot) -> Vec<String> {
+        let mib = |kib: u64| kib / 1024;
+        let [load1, load5, load15] = snapshot.load_average;
+        let uptime = Duration::seconds(snapshot.uptime_secs as i64);
+
+        let mut lines = vec![
+            tr!("snapshot.load",
+                one = format!("{:.2}", load1),
+                five = format!("{:.2}", load5),
+                fifteen = format!("{:.2}", load15)),
+            tr!("snapshot.memory",
+                available = mib(snapshot.mem_available_kib),
+                total = mib(snapshot.mem_total_kib)),
+            tr!("snapshot.swap",
+                free = mib(snapshot.swap_free_kib),
+                total = mib(snapshot.swap_total_kib)),
+            tr!("snapshot.uptime",
+                days = uptime.num_days(),
+                hours = format!("{:02}", uptime.num_hours() % 24),
+                minutes = format!("{:02}", uptime.num_minutes() % 60)),
+            tr!("snapshot.kernel", version = snapshot.kernel),
+        ];
+
+        lines.extend(snapshot.top_processes.iter().map(|process| tr!(
+            "snapshot.process",
+            name = process.name,
+            pid = process.pid,
+            size = mib(process.rss_kib))));
+
+        lines
+    }
+
+    fn hook_outcome_text(outcome: &HookOutcome, time_display: TimeDisplay) -> String {
+        let started = Self::time_text(outcome.started, time_display, "%H:%M:%S");
+        let status = match outcome.status {
+            HookStatus::Exited(code) if outcome.succeeded() => tr!("hooks.done", code = code),
+            HookStatus::Exited(code) => tr!("hooks.failed", code = code),
+            HookStatus::Killed => tr!("hooks.killed").into(),
+            HookStatus::TimedOut => tr!("hooks.timed-out").into(),
+            HookStatus::NotStarted => tr!("hooks.not-started").into(),
+        };
+
+        // the last line is usually the one that tells what happened
+        match outcome.output.lines().rev().find(|line| !line.trim().is_empty()) {
+            Some(line) => tr!("hooks.outcome-output",
+                              time = started,
+                              hook = outcome.hook,
+                              status = status,
+                              output = line.trim()),
+            None => tr!("hooks.outcome", time = started, hook = outcome.hook, status = status),
+        }
+    }
+
+    fn spool_outcome_text(outcome: &SpoolOutcome, time_display: TimeDisplay) -> String {
+        let time = Self::time_text(outcome.when, time_display, "%H:%M:%S");
+
+        match (&outcome.result, outcome.filed) {
+            (Ok(_), true) => tr!("spool.recorded", time = time, file = outcome.file),
+            (Ok(_), false) => tr!("spool.recorded-stuck", time = time, file = outcome.file),
+            (Err(error), true) => tr!("spool.failed", time = time, file = outcome.file, error = error),
+            (Err(error), false) => tr!("spool.failed-stuck", time = time, file = outcome.file, error = error),
         }
     }
 
-    fn make_entry<'a, 'b>(builder: &UiBuilder, entry: &'a Record) -> UiElement!(for<'b>) {
+    fn import_error_text(err: &ImportError) -> String {
+        match err {
+            ImportError::FileError => tr!("import.read-error").into(),
+            ImportError::FormatError => tr!("import.format-error").into(),
+            ImportError::MappingError => tr!("import.mapping-error", field = CsvField::When.label()),
+        }
+    }
+
+    fn make_entry<'a>(builder: &UiBuilder,
+                      state: &'a mut button::State,
+                      entry: &Record,
+                      local_origin: &str,
+                      time_display: TimeDisplay) -> UiElement!(for<'a>) {
+        builder.list_item(state,
+                          builder.tagged(builder.label(Self::entry_text(entry, local_origin, time_display)), &entry.tags),
+                          Message::EntrySelected(entry.id.clone()))
+    }
+
+    fn time_text(when: DateTime<Utc>, time_display: TimeDisplay, format: &str) -> String {
+        match time_display {
+            TimeDisplay::Local => when.with_timezone(&Local).format(format).to_string(),
+            TimeDisplay::Utc => when.format(format).to_string(),
+        }
+    }
+
+    fn entry_text(entry: &Record, local_origin: &str, time_display: TimeDisplay) -> String {
         //let dt_format = |d: DateTime<_>| d.format("%Y-%m-%d %H:%M:%S");
-        let time_format = |d: DateTime<_>| d.format("%H:%M:%S");
+        let time_format = |d: DateTime<Utc>| Self::time_text(d, time_display, "%H:%M:%S");
 
         let mut text = String::new();
 
         if let Some(when) = entry.frozen {
-            text.push_str(&format!("Frozen from {}", time_format(when)));
+            text = tr!("entry.frozen-from", time = time_format(when));
         }
 
         if let Some(when) = entry.busy {
             if text.is_empty() {
-                text = format!("Busy from {}", time_format(when));
+                text = tr!("entry.busy-from", time = time_format(when));
             }
             else {
-                text.push_str(&format!(", then busy at {}", time_format(when)));
+                text = tr!("entry.then-busy", text = text, time = time_format(when));
             }
         }
 
-        if text.is_empty() {
-            let when = time_format(entry.when);
-            text = match entry.how {
-                HowItWasStopped::SelfCrashed => format!("Crashed at {}", when),
-                HowItWasStopped::ManuallyKilled  => format!("Killed at {}", when),
-            };
+        let when = time_format(entry.when);
+        text = match (text.is_empty(), entry.how) {
+            (true, HowItWasStopped::SelfCrashed) => tr!("entry.crashed-at", time = when),
+            (true, HowItWasStopped::ManuallyKilled) => tr!("entry.killed-at", time = when),
+            (false, HowItWasStopped::SelfCrashed) => tr!("entry.and-crashed", text = text, time = when),
+            (false, HowItWasStopped::ManuallyKilled) => tr!("entry.and-killed", text = text, time = when),
+        };
+
+        if !entry.description.is_empty() {
+            text = tr!("entry.description", text = text, description = entry.description);
         }
-        else {
-            let when = time_format(entry.when);
-            text.push_str(&match entry.how {
-                HowItWasStopped::SelfCrashed => format!(", and crashed at {}", when),
-                HowItWasStopped::ManuallyKilled => format!(", and killed at {}", when),
-            });
+
+        if !entry.attachments.is_empty() {
+            text = trn!("entry.files", entry.attachments.len(), text = text);
         }
 
-        if !entry.description.is_empty() {
-            text.push_str(&format!(" ({})", entry.description));
+        if !entry.issues.is_empty() {
+            let issues: Vec<String> = entry.issues.iter().map(IssueRef::name).collect();
+            text = tr!("entry.issues", text = text, issues = issues.join(", "));
+        }
+
+        if !local_origin.is_empty() && entry.origin() != local_origin {
+            text = tr!("entry.origin", text = text, origin = entry.origin());
         }
 
-        builder.label(text)
+        text
     }
 
 }
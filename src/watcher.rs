@@ -0,0 +1,46 @@
+use std::collections::BTreeSet;
+
+use crate::sessions::WatchedApplication;
+
+
+// /proc is scanned on the first tick after that many seconds, whatever the tick interval
+pub const SCAN_INTERVAL_SECS: i64 = 5;
+
+
+// Applications among `watched` with at least one process alive, empty where /proc is not available.
+pub async fn scan(watched: Vec<WatchedApplication>) -> BTreeSet<String> {
+    // hundreds of small reads, kept off the executor running the window
+    async_std::task::spawn_blocking(move || {
+        let names = process_names();
+
+        watched.into_iter()
+            .filter(|watched| names.contains(&watched.process))
+            .map(|watched| watched.application)
+            .collect()
+    }).await
+}
+
+
+fn process_names() -> BTreeSet<String> {
+    let entries = match std::fs::read_dir("/proc") {
+        Ok(entries) => entries,
+        Err(_) => return BTreeSet::new(),
+    };
+
+    entries
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_name().to_str().map_or(false, |name| name.parse::<u32>().is_ok()))
+        .flat_map(|entry| {
+            let path = entry.path();
+            // comm is cut to 15 bytes, the executable gives the full name when readable
+            let comm = std::fs::read_to_string(path.join("comm"))
+                .ok()
+                .map(|comm| comm.trim().to_string());
+            let exe = std::fs::read_link(path.join("exe"))
+                .ok()
+                .and_then(|exe| exe.file_name().and_then(|name| name.to_str()).map(String::from));
+
+            comm.into_iter().chain(exe)
+        })
+        .collect()
+}
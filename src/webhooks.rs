@@ -0,0 +1,336 @@
+use std::path::PathBuf;
+use std::time::Duration as StdDuration;
+use chrono::{DateTime, Duration, Utc};
+use serde_derive::{Deserialize, Serialize};
+
+use crate::app_data::{AppData, SaveError};
+use crate::record::{Record, HowItWasStopped, dt_serde};
+
+
+// after that, a delivery is dropped from the outbox (and reported on stderr)
+const MAX_ATTEMPTS: u32 = 10;
+const REQUEST_TIMEOUT: StdDuration = StdDuration::from_secs(10);
+
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Webhook {
+    pub url: String,
+    // only records stopped this way are posted; empty means all of them
+    pub on: Vec<HowItWasStopped>,
+}
+
+
+impl Default for Webhook {
+    fn default() -> Self {
+        Self {
+            url: String::new(),
+            on: vec![],
+        }
+    }
+}
+
+
+impl Webhook {
+    pub fn wants(&self, record: &Record) -> bool {
+        self.on.is_empty() || self.on.contains(&record.how)
+    }
+}
+
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Delivery {
+    pub url: String,
+    pub payload: String,
+    pub attempts: u32,
+    #[serde(
+        serialize_with="dt_serde::serialize",
+        deserialize_with="dt_serde::deserialize")]
+    pub next_attempt: DateTime<Utc>,
+    pub last_error: Option<String>,
+}
+
+
+// Deliveries not acknowledged yet, saved so they survive a restart.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct Outbox {
+    pub deliveries: Vec<Delivery>,
+}
+
+
+impl Outbox {
+    fn path() -> PathBuf {
+        let mut path = AppData::data_dir();
+
+        path.push("outbox.json");
+
+        path
+    }
+
+    pub async fn load() -> Outbox {
+        async_std::fs::read_to_string(Self::path())
+            .await
+            .ok()
+            .and_then(|contents| serde_json::from_str(&contents).ok())
+            .unwrap_or_default()
+    }
+
+    pub async fn save(self) -> Result<(), SaveError> {
+        let json = serde_json::to_string_pretty(&self)
+            .map_err(|_| SaveError::FormatError)?;
+
+        async_std::fs::create_dir_all(AppData::data_dir())
+            .await
+            .map_err(|_| SaveError::DirectoryError)?;
+
+        async_std::fs::write(Self::path(), json)
+            .await
+            .map_err(|_| SaveError::WriteError)
+    }
+
+    pub fn enqueue(&mut self, webhooks: &[Webhook], record: &Record, now: DateTime<Utc>) -> bool {
+        let payload = payload(record);
+        let len = self.deliveries.len();
+
+        for webhook in webhooks.iter().filter(|webhook| webhook.wants(record)) {
+            self.deliveries.push(Delivery {
+                url: webhook.url.clone(),
+                payload: payload.clone(),
+                attempts: 0,
+                next_attempt: now,
+                last_error: None,
+            });
+        }
+
+        self.deliveries.len() != len
+    }
+
+    pub fn due(&self, now: DateTime<Utc>) -> Vec<Delivery> {
+        self.deliveries.iter()
+            .filter(|delivery| delivery.next_attempt <= now)
+            .cloned()
+            .collect()
+    }
+
+    // Apply the results of `deliver`: sent ones leave the outbox, failed ones
+    // are retried later with an exponential backoff (30s, 1min, 2min... up to 1h).
+    pub fn settle(&mut self, results: Vec<(Delivery, Result<(), String>)>, now: DateTime<Utc>) {
+        for (sent, result) in results {
+            let index = match self.deliveries.iter().position(|delivery| {
+                delivery.url == sent.url
+                    && delivery.payload == sent.payload
+                    && delivery.attempts == sent.attempts
+            }) {
+                Some(index) => index,
+                None => continue,
+            };
+
+            match result {
+                Ok(()) => {
+                    self.deliveries.remove(index);
+                },
+                Err(_) if sent.attempts + 1 >= MAX_ATTEMPTS => {
+                    let delivery = self.deliveries.remove(index);
+                    eprintln!("crash-recorder: giving up on webhook {} after {} attempts",
+                              delivery.url,
+                              MAX_ATTEMPTS);
+                },
+                Err(err) => {
+                    let delivery = &mut self.deliveries[index];
+                    delivery.attempts += 1;
+                    delivery.next_attempt = now + std::cmp::min(
+                        Duration::seconds(30 << (delivery.attempts - 1).min(7)),
+                        Duration::hours(1));
+                    delivery.last_error = Some(err);
+                },
+            }
+        }
+    }
+}
+
+
+fn payload(record: &Record) -> String {
+    serde_json::json!({
+        "event": "record-committed",
+        "record": record,
+        "application": {
+            "name": env!("CARGO_PKG_NAME"),
+            "version": env!("CARGO_PKG_VERSION"),
+        },
+        "host": {
+            "name": crate::host::host_name(),
+            "user": crate::host::user_name(),
+            "os": std::env::consts::OS,
+        },
+    }).to_string()
+}
+
+
+pub async fn deliver(deliveries: Vec<Delivery>) -> Vec<(Delivery, Result<(), String>)> {
+    async_std::task::spawn_blocking(move || {
+        deliveries.into_iter()
+            .map(|delivery| {
+                let result = post(&delivery);
+                (delivery, result)
+            })
+            .collect()
+    }).await
+}
+
+
+fn post(delivery: &Delivery) -> Result<(), String> {
+    let response = ureq::post(&delivery.url)
+        .set("Content-Type", "application/json")
+        .set("User-Agent", concat!(env!("CARGO_PKG_NAME"), "/", env!("CARGO_PKG_VERSION")))
+        .timeout(REQUEST_TIMEOUT)
+        .send_string(&delivery.payload);
+
+    if let Some(err) = response.synthetic_error() {
+        Err(err.to_string())
+    }
+    else if response.ok() {
+        Ok(())
+    }
+    else {
+        Err(response.status_line().to_string())
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+
+    use super::*;
+
+
+    fn outbox(url: &str, now: DateTime<Utc>) -> Outbox {
+        let mut outbox = Outbox::default();
+        let webhooks = [Webhook { url: url.into(), on: vec![] }];
+        outbox.enqueue(&webhooks, &Record::default(), now);
+        outbox
+    }
+
+    // Answers each of `statuses` to one request, and hands back the requests.
+    fn stand_in(statuses: Vec<u16>) -> (String, std::thread::JoinHandle<Vec<(String, String)>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+
+        let server = std::thread::spawn(move || {
+            statuses.into_iter().map(|status| {
+                let (stream, _) = listener.accept().unwrap();
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut head = String::new();
+                let mut length = 0;
+
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).unwrap();
+                    if line.trim().is_empty() {
+                        break;
+                    }
+                    if let Some(value) = line.to_lowercase().strip_prefix("content-length:") {
+                        length = value.trim().parse().unwrap();
+                    }
+                    head.push_str(&line);
+                }
+
+                let mut body = vec![0; length];
+                reader.read_exact(&mut body).unwrap();
+                write!(&stream, "HTTP/1.1 {} Whatever\r\nContent-Length: 0\r\nConnection: close\r\n\r\n", status).unwrap();
+
+                (head, String::from_utf8(body).unwrap())
+            }).collect()
+        });
+
+        (url, server)
+    }
+
+    #[test]
+    fn enqueue_only_wanted() {
+        let now = Utc::now();
+        let mut outbox = Outbox::default();
+        let webhooks = [
+            Webhook { url: "http://a".into(), on: vec![HowItWasStopped::ManuallyKilled] },
+            Webhook { url: "http://b".into(), on: vec![] },
+        ];
+
+        assert!(outbox.enqueue(&webhooks, &Record { how: HowItWasStopped::SelfCrashed, ..Record::default() }, now));
+        assert_eq!(outbox.deliveries.len(), 1);
+        assert_eq!(outbox.deliveries[0].url, "http://b");
+        assert!(!outbox.enqueue(&webhooks[..1], &Record { how: HowItWasStopped::SelfCrashed, ..Record::default() }, now));
+    }
+
+    #[test]
+    fn settle_backs_off_then_gives_up() {
+        let now = Utc::now();
+        let mut outbox = outbox("http://a", now);
+        let mut expected = vec![];
+
+        for _ in 0..MAX_ATTEMPTS - 1 {
+            let due = outbox.deliveries.clone();
+            outbox.settle(due.into_iter().map(|delivery| (delivery, Err("refused".into()))).collect(), now);
+
+            let delivery = &outbox.deliveries[0];
+            assert_eq!(delivery.last_error.as_deref(), Some("refused"));
+            assert!(outbox.due(now).is_empty());
+            expected.push((delivery.next_attempt - now).num_seconds());
+        }
+
+        assert_eq!(expected, vec![30, 60, 120, 240, 480, 960, 1920, 3600, 3600]);
+
+        let due = outbox.deliveries.clone();
+        outbox.settle(due.into_iter().map(|delivery| (delivery, Err("refused".into()))).collect(), now);
+        assert!(outbox.deliveries.is_empty());
+    }
+
+    #[test]
+    fn settle_removes_sent_and_ignores_stale() {
+        let now = Utc::now();
+        let mut outbox = outbox("http://a", now);
+        let sent = outbox.deliveries[0].clone();
+
+        // an answer for an attempt that was already settled
+        let stale = Delivery { attempts: 3, ..sent.clone() };
+        outbox.settle(vec![(stale, Ok(()))], now);
+        assert_eq!(outbox.deliveries.len(), 1);
+
+        outbox.settle(vec![(sent, Ok(()))], now);
+        assert!(outbox.deliveries.is_empty());
+    }
+
+    #[test]
+    fn deliver_to_stand_in() {
+        let now = Utc::now();
+        let (url, server) = stand_in(vec![200, 500]);
+        let mut outbox = outbox(&url, now);
+        outbox.enqueue(&[Webhook { url: url.clone(), on: vec![] }], &Record::default(), now);
+
+        let results = async_std::task::block_on(deliver(outbox.due(now)));
+        assert!(results[0].1.is_ok());
+        assert!(results[1].1.as_ref().unwrap_err().contains("500"));
+
+        outbox.settle(results, now);
+        assert_eq!(outbox.deliveries.len(), 1);
+        assert_eq!(outbox.deliveries[0].attempts, 1);
+
+        let requests = server.join().unwrap();
+        let (head, body) = &requests[0];
+        assert!(head.starts_with("POST /hook HTTP/1.1"));
+        assert!(head.to_lowercase().contains("content-type: application/json"));
+        let payload: serde_json::Value = serde_json::from_str(body).unwrap();
+        assert_eq!(payload["event"], "record-committed");
+    }
+
+    #[test]
+    fn deliver_to_nobody() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        drop(listener);
+
+        let now = Utc::now();
+        let results = async_std::task::block_on(deliver(outbox(&url, now).due(now)));
+        assert!(results[0].1.is_err());
+    }
+}